@@ -0,0 +1,344 @@
+use crate::entry::{Game, Rom, GAME_COLUMNS, ROM_COLUMNS};
+use crate::error::{Result, ShiragameError};
+use crate::{SCHEMA_VERSION_MAJOR, SCHEMA_VERSION_MINOR};
+
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, ToSql};
+use shiratsu_dat::{DumpQuality, Serial};
+use shiratsu_stone::PlatformId;
+
+use std::path::Path;
+
+/// The contents of the `shiragame` meta table.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ShiragameMeta {
+    schema_version: String,
+    stone_version: String,
+    generated: String,
+    release: String,
+    aggregator: String,
+}
+
+impl ShiragameMeta {
+    /// The version of the schema used by the database.
+    pub fn schema_version(&self) -> &str {
+        &self.schema_version
+    }
+    /// The version of the Stone definitions used by the database.
+    pub fn stone_version(&self) -> &str {
+        &self.stone_version
+    }
+    /// The time the database was generated, as a Unix timestamp.
+    pub fn generated(&self) -> &str {
+        &self.generated
+    }
+    /// The UUID that identifies this release of the database.
+    pub fn release(&self) -> &str {
+        &self.release
+    }
+    /// The aggregator that generated the database.
+    pub fn aggregator(&self) -> &str {
+        &self.aggregator
+    }
+}
+
+/// A read-only shiragame database.
+pub struct ShiragameDatabase {
+    conn: Connection,
+    meta: ShiragameMeta,
+}
+
+impl ShiragameDatabase {
+    /// Opens the shiragame database at the given path as read-only.
+    ///
+    /// Fails if the database is not a shiragame database, or if its schema version
+    /// is not compatible with this version of the crate.
+    pub fn open<T: AsRef<Path>>(path: T) -> Result<ShiragameDatabase> {
+        ShiragameDatabase::from_connection(Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY,
+        )?)
+    }
+
+    pub(crate) fn from_connection(conn: Connection) -> Result<ShiragameDatabase> {
+        let meta = conn
+            .query_row(
+                "SELECT shiragame, schema_version, stone_version, generated, release, aggregator FROM shiragame",
+                params![],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        ShiragameMeta {
+                            schema_version: row.get(1)?,
+                            stone_version: row.get(2)?,
+                            generated: row.get(3)?,
+                            release: row.get(4)?,
+                            aggregator: row.get(5)?,
+                        },
+                    ))
+                },
+            )
+            .optional()
+            .map_err(|_| ShiragameError::NotShiragameError)?;
+
+        let meta = match meta {
+            Some((shiragame, meta)) if shiragame == "shiragame" => meta,
+            _ => return Err(ShiragameError::NotShiragameError),
+        };
+        if !is_supported_version(&meta.schema_version) {
+            return Err(ShiragameError::SchemaVersionError(meta.schema_version));
+        }
+        Ok(ShiragameDatabase { conn, meta })
+    }
+
+    /// The contents of the `shiragame` meta table.
+    pub fn meta(&self) -> &ShiragameMeta {
+        &self.meta
+    }
+
+    /// Gets the game entry with the given ID.
+    pub fn game(&self, game_id: i64) -> Result<Option<Game>> {
+        Ok(self
+            .conn
+            .query_row(
+                &format!("SELECT {} FROM game WHERE game.game_id = ?", GAME_COLUMNS),
+                params![game_id],
+                Game::from_row,
+            )
+            .optional()?)
+    }
+
+    /// Gets the ROMs of the game entry.
+    pub fn roms(&self, game: &Game) -> Result<Vec<Rom>> {
+        let mut stmt = self.conn.prepare_cached(&format!(
+            "SELECT {} FROM rom WHERE rom.game_id = ? ORDER BY rom.rowid",
+            ROM_COLUMNS
+        ))?;
+        let roms = stmt.query_map(params![game.game_id()], Rom::from_row)?;
+        Ok(roms.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Gets the serials of the game entry, as published by the cataloguing organization.
+    pub fn serials(&self, game: &Game) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT serial FROM serial WHERE game_id = ? ORDER BY rowid")?;
+        let serials = stmt.query_map(params![game.game_id()], |row| row.get(0))?;
+        Ok(serials.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Gets the languages of the game entry, as lowercase ISO 639-1 codes.
+    pub fn languages(&self, game: &Game) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT language FROM language WHERE game_id = ? ORDER BY rowid")?;
+        let languages = stmt.query_map(params![game.game_id()], |row| row.get(0))?;
+        Ok(languages.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Gets the publishers of the game entry, as they appear in its name.
+    pub fn publishers(&self, game: &Game) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT publisher FROM publisher WHERE game_id = ? ORDER BY rowid")?;
+        let publishers = stmt.query_map(params![game.game_id()], |row| row.get(0))?;
+        Ok(publishers.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Gets the dump flags of the game entry.
+    ///
+    /// Flags that are not known to this version of the crate are skipped.
+    pub fn dump_flags(&self, game: &Game) -> Result<Vec<DumpQuality>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT flag FROM dump_flag WHERE game_id = ? ORDER BY rowid")?;
+        let flags = stmt.query_map(params![game.game_id()], |row| row.get::<_, String>(0))?;
+        Ok(flags
+            .collect::<rusqlite::Result<Vec<_>>>()?
+            .iter()
+            .filter_map(|flag| match flag.as_str() {
+                "bad" => Some(DumpQuality::BadDump),
+                "alternate" => Some(DumpQuality::Alternate),
+                "hack" => Some(DumpQuality::Hack),
+                "trainer" => Some(DumpQuality::Trainer),
+                "cracked" => Some(DumpQuality::Cracked),
+                "fixed" => Some(DumpQuality::Fixed),
+                "pirate" => Some(DumpQuality::Pirate),
+                "translation" => Some(DumpQuality::Translation),
+                "verified" => Some(DumpQuality::Verified),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Finds the game entries for the platform with the language, given as an ISO 639-1 code.
+    pub fn games_by_language(&self, language: &str, platform: &PlatformId) -> Result<Vec<Game>> {
+        self.query_games(
+            &format!(
+                "SELECT DISTINCT {} FROM game INNER JOIN language ON language.game_id = game.game_id
+                WHERE language.language = ? AND game.platform_id = ? ORDER BY game.game_id",
+                GAME_COLUMNS
+            ),
+            &[&language.to_ascii_lowercase(), &platform.as_ref()],
+        )
+    }
+
+    /// Finds the game entries with a ROM matching the hash.
+    ///
+    /// The kind of hash is determined by its length, so that the hash may be
+    /// either a CRC32, XXH3, MD5, SHA1, or SHA256 hash as a hex string.
+    pub fn games_by_hash(&self, hash: &str) -> Result<Vec<Game>> {
+        let column = match hash.len() {
+            _ if !hash.chars().all(|c| c.is_ascii_hexdigit()) => None,
+            8 => Some("crc"),
+            16 => Some("xxh3"),
+            32 => Some("md5"),
+            40 => Some("sha1"),
+            64 => Some("sha256"),
+            _ => None,
+        }
+        .ok_or_else(|| ShiragameError::InvalidHashError(hash.to_string()))?;
+
+        self.query_games(
+            &format!(
+                "SELECT DISTINCT {} FROM game INNER JOIN rom ON rom.game_id = game.game_id
+                WHERE rom.{} = ? ORDER BY game.game_id",
+                GAME_COLUMNS, column
+            ),
+            &[&hash.to_ascii_lowercase()],
+        )
+    }
+
+    /// Finds the game entries for the platform with the serial, after normalizing the serial
+    /// with the normalization rules of the platform.
+    pub fn games_by_serial(&self, serial: &str, platform: &PlatformId) -> Result<Vec<Game>> {
+        let serial = Serial::new(serial.to_string());
+        let normalized = serial.as_normalized(platform);
+        self.query_games(
+            &format!(
+                "SELECT DISTINCT {} FROM game INNER JOIN serial ON serial.game_id = game.game_id
+                WHERE serial.normalized = ? AND game.platform_id = ? ORDER BY game.game_id",
+                GAME_COLUMNS
+            ),
+            &[&normalized.as_ref().as_ref(), &platform.as_ref()],
+        )
+    }
+
+    /// Searches the titles of every game entry with an FTS5 query, best matches first.
+    pub fn search_titles(&self, query: &str) -> Result<Vec<Game>> {
+        self.query_games(
+            &format!(
+                "SELECT {} FROM game_title INNER JOIN game ON game.game_id = game_title.rowid
+                WHERE game_title MATCH ? ORDER BY rank",
+                GAME_COLUMNS
+            ),
+            &[&query],
+        )
+    }
+
+    fn query_games(&self, sql: &str, params: &[&dyn ToSql]) -> Result<Vec<Game>> {
+        let mut stmt = self.conn.prepare_cached(sql)?;
+        let games = stmt.query_map(params, Game::from_row)?;
+        Ok(games.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+/// Whether the schema version has the same major version, and at least the same minor version,
+/// as the schema this crate reads.
+fn is_supported_version(version: &str) -> bool {
+    let mut parts = version.split('.').map(|p| p.parse::<u32>());
+    match (parts.next(), parts.next()) {
+        (Some(Ok(major)), Some(Ok(minor))) => major == SCHEMA_VERSION_MAJOR && minor >= SCHEMA_VERSION_MINOR,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shiratsu_dat::{DevelopmentStatus, RomStatus};
+    use std::convert::TryInto;
+
+    fn test_database(schema_version: &str) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE game (game_id INTEGER PRIMARY KEY, platform_id TEXT NOT NULL, entry_name TEXT NOT NULL,
+                entry_title TEXT, release_title TEXT, region TEXT NOT NULL, part_number INTEGER,
+                is_unlicensed BOOLEAN NOT NULL, is_demo BOOLEAN NOT NULL, is_system BOOLEAN NOT NULL,
+                version TEXT, status TEXT, naming_convention TEXT, source TEXT NOT NULL, dat_id INTEGER NOT NULL,
+                release_year INTEGER, release_date TEXT);
+            CREATE TABLE rom (file_name TEXT NOT NULL, mimetype TEXT, md5 TEXT, crc TEXT, sha1 TEXT,
+                sha256 TEXT, xxh3 TEXT, size INTEGER NOT NULL, status TEXT NOT NULL, game_id INTEGER NOT NULL);
+            CREATE TABLE serial (serial TEXT NOT NULL, normalized TEXT NOT NULL, game_id INTEGER NOT NULL);
+            CREATE TABLE language (language TEXT NOT NULL, game_id INTEGER NOT NULL);
+            CREATE TABLE publisher (publisher TEXT NOT NULL, game_id INTEGER NOT NULL);
+            CREATE TABLE dump_flag (flag TEXT NOT NULL, game_id INTEGER NOT NULL);
+            CREATE TABLE shiragame (shiragame TEXT, schema_version TEXT, stone_version TEXT, generated TEXT,
+                release TEXT, aggregator TEXT);
+            INSERT INTO game VALUES (1, 'SONY_PSX', 'Ridge Racer (USA)', 'Ridge Racer', 'Ridge Racer', 'US', NULL,
+                0, 0, 0, NULL, 'release', 'NoIntro', 'Redump', 1, 1995, '1995-05');
+            INSERT INTO game VALUES (2, 'SONY_PSX', 'Ridge Racer Revolution (Japan) (Beta)', 'Ridge Racer Revolution',
+                'Ridge Racer Revolution', 'JP', NULL, 0, 0, 0, NULL, 'prerelease', 'NoIntro', 'Redump', 1, NULL, '199x');
+            INSERT INTO rom VALUES ('Ridge Racer (USA).bin', NULL, '5a0c5b8c2a6a2b8a6a38e3a2ebb4f9a6', 'c2e2aba4',
+                'ec0a2a4a1d2f3f8b8a4e1e5c2b3d8f8e6a5d4c3b',
+                '0d3b5e0b4f5d1b1c5e6a7f8e9d0c1b2a3f4e5d6c7b8a9f0e1d2c3b4a5f6e7d8c', '8a2f3b9c1d4e5f60', 1024, 'verified', 1);
+            INSERT INTO serial VALUES ('SCUS 94300', 'SCUS-94300', 1);
+            INSERT INTO language VALUES ('ja', 2);
+            INSERT INTO publisher VALUES ('Namco', 1);
+            INSERT INTO dump_flag VALUES ('verified', 1);
+            INSERT INTO dump_flag VALUES ('not a flag', 1);
+            CREATE VIRTUAL TABLE game_title USING fts5 (entry_title, release_title, content = 'game', content_rowid = 'game_id');
+            INSERT INTO game_title (game_title) VALUES ('rebuild');",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO shiragame VALUES ('shiragame', ?, '11.2.0', '0', 'release', 'shiratsu')",
+            params![schema_version],
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn checks_schema_version() {
+        assert!(ShiragameDatabase::from_connection(test_database("3.9.0")).is_ok());
+        assert!(ShiragameDatabase::from_connection(test_database("3.10.1")).is_ok());
+        match ShiragameDatabase::from_connection(test_database("3.8.0")) {
+            Err(ShiragameError::SchemaVersionError(version)) => assert_eq!("3.8.0", version),
+            _ => panic!("expected schema version error"),
+        }
+        match ShiragameDatabase::from_connection(Connection::open_in_memory().unwrap()) {
+            Err(ShiragameError::NotShiragameError) => {}
+            _ => panic!("expected not shiragame error"),
+        }
+    }
+
+    #[test]
+    fn finds_games() {
+        let db = ShiragameDatabase::from_connection(test_database("3.9.0")).unwrap();
+        assert_eq!("11.2.0", db.meta().stone_version());
+
+        let games = db.games_by_hash("C2E2ABA4").unwrap();
+        assert_eq!(vec!["Ridge Racer (USA)"], games.iter().map(|g| g.entry_name()).collect::<Vec<_>>());
+        assert_eq!(1, db.games_by_hash("ec0a2a4a1d2f3f8b8a4e1e5c2b3d8f8e6a5d4c3b").unwrap().len());
+        let sha256 = "0D3B5E0B4F5D1B1C5E6A7F8E9D0C1B2A3F4E5D6C7B8A9F0E1D2C3B4A5F6E7D8C";
+        assert_eq!(1, db.games_by_hash(sha256).unwrap().len());
+        assert_eq!(1, db.games_by_hash("8A2F3B9C1D4E5F60").unwrap().len());
+        assert_eq!(Some("8a2f3b9c1d4e5f60"), db.roms(&games[0]).unwrap()[0].hash_xxh3());
+        assert_eq!(1024, db.roms(&games[0]).unwrap()[0].size());
+        assert_eq!(RomStatus::Verified, db.roms(&games[0]).unwrap()[0].status());
+        assert!(db.games_by_hash("not a hash").is_err());
+
+        let platform: &PlatformId = "SONY_PSX".try_into().unwrap();
+        assert_eq!(1, db.games_by_serial("SCUS-94300", platform).unwrap().len());
+        assert_eq!(vec!["SCUS 94300"], db.serials(&games[0]).unwrap());
+        assert_eq!(vec!["Namco"], db.publishers(&games[0]).unwrap());
+        assert_eq!(vec![DumpQuality::Verified], db.dump_flags(&games[0]).unwrap());
+        assert_eq!((Some(1995), Some("1995-05")), (games[0].release_year(), games[0].release_date()));
+
+        let games = db.search_titles("revol*").unwrap();
+        assert_eq!(2, games[0].game_id());
+        assert_eq!(DevelopmentStatus::Prerelease, games[0].development_status());
+        assert_eq!(vec!["ja"], db.languages(&games[0]).unwrap());
+        assert_eq!(2, db.games_by_language("JA", platform).unwrap()[0].game_id());
+    }
+}
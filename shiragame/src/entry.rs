@@ -0,0 +1,203 @@
+use rusqlite::Row;
+use shiratsu_dat::{DevelopmentStatus, RomStatus};
+use shiratsu_naming::region::Region;
+
+/// A single row of the `game` table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Game {
+    pub(crate) game_id: i64,
+    pub(crate) platform_id: String,
+    pub(crate) entry_name: String,
+    pub(crate) entry_title: Option<String>,
+    pub(crate) release_title: Option<String>,
+    pub(crate) region: Vec<Region>,
+    pub(crate) part_number: Option<i32>,
+    pub(crate) is_unlicensed: bool,
+    pub(crate) is_demo: bool,
+    pub(crate) is_system: bool,
+    pub(crate) version: Option<String>,
+    pub(crate) release_year: Option<i32>,
+    pub(crate) release_date: Option<String>,
+    pub(crate) status: DevelopmentStatus,
+    pub(crate) naming_convention: String,
+    pub(crate) source: String,
+}
+
+pub(crate) const GAME_COLUMNS: &str = "game.game_id, game.platform_id, game.entry_name, game.entry_title,
+    game.release_title, game.region, game.part_number, game.is_unlicensed, game.is_demo, game.is_system,
+    game.version, game.status, game.naming_convention, game.source, game.release_year, game.release_date";
+
+impl Game {
+    pub(crate) fn from_row(row: &Row) -> rusqlite::Result<Game> {
+        let region: String = row.get(5)?;
+        let status: Option<String> = row.get(11)?;
+        Ok(Game {
+            game_id: row.get(0)?,
+            platform_id: row.get(1)?,
+            entry_name: row.get(2)?,
+            entry_title: row.get(3)?,
+            release_title: row.get(4)?,
+            region: Region::from_region_string(region),
+            part_number: row.get(6)?,
+            is_unlicensed: row.get(7)?,
+            is_demo: row.get(8)?,
+            is_system: row.get(9)?,
+            version: row.get(10)?,
+            status: match status.as_deref() {
+                Some("prerelease") => DevelopmentStatus::Prerelease,
+                Some("prototype") => DevelopmentStatus::Prototype,
+                _ => DevelopmentStatus::Release,
+            },
+            naming_convention: row.get::<_, Option<String>>(12)?.unwrap_or_default(),
+            source: row.get(13)?,
+            release_year: row.get(14)?,
+            release_date: row.get(15)?,
+        })
+    }
+
+    /// The ID of the game entry, which is only meaningful within the same database.
+    pub fn game_id(&self) -> i64 {
+        self.game_id
+    }
+    /// The Stone platform ID of the game entry.
+    pub fn platform_id(&self) -> &str {
+        &self.platform_id
+    }
+    /// The name of the game entry, as is.
+    pub fn entry_name(&self) -> &str {
+        &self.entry_name
+    }
+    /// The canonical name of the game entry, with any metadata flags removed.
+    pub fn entry_title(&self) -> Option<&str> {
+        self.entry_title.as_deref()
+    }
+    /// The distribution or release name of the game entry.
+    pub fn release_title(&self) -> Option<&str> {
+        self.release_title.as_deref()
+    }
+    /// The region the game was released under.
+    pub fn region(&self) -> &[Region] {
+        &self.region
+    }
+    /// If this entry is split into multiple parts, the part number of this entry.
+    pub fn part_number(&self) -> Option<i32> {
+        self.part_number
+    }
+    /// Whether or not this game is unlicensed.
+    pub fn is_unlicensed(&self) -> bool {
+        self.is_unlicensed
+    }
+    /// Whether or not this game is a sample or a demo version of a full game.
+    pub fn is_demo(&self) -> bool {
+        self.is_demo
+    }
+    /// Whether or not this game is a system update or BIOS file.
+    pub fn is_system(&self) -> bool {
+        self.is_system
+    }
+    /// The version of this game entry, if any.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+    /// The year the game was released, if the year is known in full.
+    pub fn release_year(&self) -> Option<i32> {
+        self.release_year
+    }
+    /// The date the game was released, which may be partial, such as `19xx` or `1997-10`.
+    pub fn release_date(&self) -> Option<&str> {
+        self.release_date.as_deref()
+    }
+    /// The development status of this game entry.
+    pub fn development_status(&self) -> DevelopmentStatus {
+        self.status
+    }
+    /// The naming convention the entry name conforms to.
+    pub fn naming_convention(&self) -> &str {
+        &self.naming_convention
+    }
+    /// The cataloguing organization the game entry was sourced from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+/// A single row of the `rom` table.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Rom {
+    pub(crate) file_name: String,
+    pub(crate) mimetype: Option<String>,
+    pub(crate) md5: Option<String>,
+    pub(crate) crc: Option<String>,
+    pub(crate) sha1: Option<String>,
+    pub(crate) sha256: Option<String>,
+    pub(crate) xxh3: Option<String>,
+    pub(crate) size: i64,
+    pub(crate) status: RomStatus,
+    pub(crate) game_id: i64,
+}
+
+pub(crate) const ROM_COLUMNS: &str =
+    "rom.file_name, rom.mimetype, rom.md5, rom.crc, rom.sha1, rom.size, rom.game_id, rom.status, rom.sha256, rom.xxh3";
+
+impl Rom {
+    pub(crate) fn from_row(row: &Row) -> rusqlite::Result<Rom> {
+        Ok(Rom {
+            file_name: row.get(0)?,
+            mimetype: row.get(1)?,
+            md5: row.get(2)?,
+            crc: row.get(3)?,
+            sha1: row.get(4)?,
+            size: row.get(5)?,
+            game_id: row.get(6)?,
+            sha256: row.get(8)?,
+            xxh3: row.get(9)?,
+            status: match row.get::<_, Option<String>>(7)?.as_deref() {
+                Some("baddump") => RomStatus::BadDump,
+                Some("nodump") => RomStatus::NoDump,
+                Some("verified") => RomStatus::Verified,
+                _ => RomStatus::Good,
+            },
+        })
+    }
+
+    /// The canonical file name of the ROM.
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+    /// The Stone mimetype of the ROM, if known.
+    pub fn mimetype(&self) -> Option<&str> {
+        self.mimetype.as_deref()
+    }
+    /// The MD5 hash of the ROM.
+    pub fn hash_md5(&self) -> Option<&str> {
+        self.md5.as_deref()
+    }
+    /// The CRC32 hash of the ROM.
+    pub fn hash_crc(&self) -> Option<&str> {
+        self.crc.as_deref()
+    }
+    /// The SHA1 hash of the ROM.
+    pub fn hash_sha1(&self) -> Option<&str> {
+        self.sha1.as_deref()
+    }
+    /// The SHA256 hash of the ROM, if the source DAT has one.
+    pub fn hash_sha256(&self) -> Option<&str> {
+        self.sha256.as_deref()
+    }
+    /// The 64-bit XXH3 hash of the ROM, if the source DAT has one.
+    pub fn hash_xxh3(&self) -> Option<&str> {
+        self.xxh3.as_deref()
+    }
+    /// The size of the ROM in bytes.
+    pub fn size(&self) -> i64 {
+        self.size
+    }
+    /// The dump status of the ROM.
+    pub fn status(&self) -> RomStatus {
+        self.status
+    }
+    /// The ID of the game entry this ROM belongs to.
+    pub fn game_id(&self) -> i64 {
+        self.game_id
+    }
+}
@@ -0,0 +1,39 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+#[derive(Debug)]
+pub enum ShiragameError {
+    SqliteError(rusqlite::Error),
+    NotShiragameError,
+    SchemaVersionError(String),
+    InvalidHashError(String),
+}
+
+impl From<rusqlite::Error> for ShiragameError {
+    fn from(err: rusqlite::Error) -> Self {
+        ShiragameError::SqliteError(err)
+    }
+}
+
+impl Error for ShiragameError {}
+
+impl Display for ShiragameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ShiragameError::SqliteError(err) => write!(f, "SQLite Error: {}", err),
+            ShiragameError::NotShiragameError => write!(f, "The database is not a shiragame database."),
+            ShiragameError::SchemaVersionError(version) => write!(
+                f,
+                "Unsupported shiragame schema version {}, expected ^{}.{}.0.",
+                version,
+                crate::SCHEMA_VERSION_MAJOR,
+                crate::SCHEMA_VERSION_MINOR
+            ),
+            ShiragameError::InvalidHashError(hash) => {
+                write!(f, "\"{}\" is not a CRC32, XXH3, MD5, SHA1, or SHA256 hash.", hash)
+            }
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ShiragameError>;
@@ -0,0 +1,13 @@
+//! Typed, read-only access to shiragame databases generated by shiratsu.
+mod database;
+mod entry;
+mod error;
+
+pub use database::{ShiragameDatabase, ShiragameMeta};
+pub use entry::{Game, Rom};
+pub use error::*;
+
+/// The major version of the shiragame schema that can be read.
+pub const SCHEMA_VERSION_MAJOR: u32 = 3;
+/// The earliest minor version of the shiragame schema that can be read.
+pub const SCHEMA_VERSION_MINOR: u32 = 9;
@@ -0,0 +1,480 @@
+use crate::error::{DatError, Result as DatResult};
+use crate::reader::decode_line;
+use crate::{DatHeader, GameEntry};
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use serde::Deserialize;
+use serde::forward_to_deserialize_any;
+use std::collections::VecDeque;
+use std::convert::TryInto;
+use std::fmt::{self, Display, Formatter};
+use std::io::BufRead;
+
+#[derive(Debug)]
+pub(crate) struct ClrMameProError(pub(crate) String);
+
+impl Display for ClrMameProError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ClrMameProError {}
+
+impl de::Error for ClrMameProError {
+    fn custom<T: Display>(msg: T) -> Self {
+        ClrMameProError(msg.to_string())
+    }
+}
+
+impl From<ClrMameProError> for DatError {
+    fn from(err: ClrMameProError) -> Self {
+        DatError::ParseError(format!("Error parsing ClrMamePro DAT: {}", err))
+    }
+}
+
+pub(crate) type Result<T> = std::result::Result<T, ClrMameProError>;
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Atom(String),
+}
+
+/// A value in a ClrMamePro DAT, either a single string or a parenthesized list of key-value pairs.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Node {
+    Value(String),
+    List(Vec<(String, Node)>),
+}
+
+/// Splits a line of a ClrMamePro DAT into tokens.
+///
+/// Quoted strings may contain whitespace and parentheses, but may not span multiple lines.
+fn tokenize_line(line: &str, tokens: &mut VecDeque<Token>) -> Result<()> {
+    let mut rest = line.trim_start();
+    while !rest.is_empty() {
+        if let Some(next) = rest.strip_prefix('(') {
+            tokens.push_back(Token::Open);
+            rest = next;
+        } else if let Some(next) = rest.strip_prefix(')') {
+            tokens.push_back(Token::Close);
+            rest = next;
+        } else if let Some(next) = rest.strip_prefix('"') {
+            let end = next
+                .find('"')
+                .ok_or_else(|| ClrMameProError(format!("Unterminated string in line {}", line.trim())))?;
+            tokens.push_back(Token::Atom(next[..end].to_string()));
+            rest = &next[end + 1..];
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == '(' || c == ')')
+                .unwrap_or(rest.len());
+            tokens.push_back(Token::Atom(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+        rest = rest.trim_start();
+    }
+    Ok(())
+}
+
+/// Reads `game` blocks out of a ClrMamePro DAT, one at a time.
+pub(crate) struct ClrMameProReader<R: BufRead> {
+    reader: R,
+    line: Vec<u8>,
+    tokens: VecDeque<Token>,
+    pending: Option<Node>,
+    parse_game: fn(Node) -> DatResult<GameEntry>,
+    done: bool,
+}
+
+impl<R: BufRead> ClrMameProReader<R> {
+    pub(crate) fn new<G: DeserializeOwned + TryInto<GameEntry, Error = DatError>>(
+        f: R,
+    ) -> ClrMameProReader<R> {
+        ClrMameProReader {
+            reader: f,
+            line: Vec::new(),
+            tokens: VecDeque::new(),
+            pending: None,
+            parse_game: parse_game::<G>,
+            done: false,
+        }
+    }
+
+    /// Reads up to the end of the `clrmamepro` block.
+    ///
+    /// If a `game` block is encountered first, it is kept to be yielded first.
+    pub(crate) fn read_header(&mut self) -> DatResult<Option<DatHeader>> {
+        while let Some((name, block)) = self.read_block()? {
+            match name.as_str() {
+                "clrmamepro" => {
+                    return Ok(Some(DatHeader::deserialize(Field(vec![nest_header_options(block)]))?))
+                }
+                "game" => {
+                    self.pending = Some(block);
+                    return Ok(None);
+                }
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>> {
+        while self.tokens.is_empty() {
+            self.line.clear();
+            let read = self
+                .reader
+                .read_until(b'\n', &mut self.line)
+                .map_err(|err| ClrMameProError(err.to_string()))?;
+            if read == 0 {
+                return Ok(None);
+            }
+            tokenize_line(&decode_line(&self.line), &mut self.tokens)?;
+        }
+        Ok(self.tokens.pop_front())
+    }
+
+    /// Reads a top level block of the form `name ( key value ... )`.
+    fn read_block(&mut self) -> Result<Option<(String, Node)>> {
+        match self.next_token()? {
+            None => Ok(None),
+            Some(Token::Atom(name)) => match self.next_token()? {
+                Some(Token::Open) => Ok(Some((name, Node::List(self.read_list()?)))),
+                _ => Err(ClrMameProError(format!("Expected ( after {}", name))),
+            },
+            Some(_) => Err(ClrMameProError("Expected the name of a block".to_string())),
+        }
+    }
+
+    /// Reads key-value pairs up to the closing parenthesis of the current list.
+    fn read_list(&mut self) -> Result<Vec<(String, Node)>> {
+        let mut entries = Vec::new();
+        loop {
+            match self.next_token()? {
+                Some(Token::Close) => return Ok(entries),
+                Some(Token::Atom(key)) => {
+                    let value = match self.next_token()? {
+                        Some(Token::Atom(value)) => Node::Value(value),
+                        Some(Token::Open) => Node::List(self.read_list()?),
+                        Some(Token::Close) => {
+                            return Err(ClrMameProError(format!("Expected a value for {}", key)))
+                        }
+                        None => break,
+                    };
+                    entries.push((key, value));
+                }
+                Some(Token::Open) => return Err(ClrMameProError("Expected a key, found (".to_string())),
+                None => break,
+            }
+        }
+        Err(ClrMameProError("Unexpected end of file".to_string()))
+    }
+
+    fn read_game(&mut self) -> Result<Option<Node>> {
+        while let Some((name, block)) = self.read_block()? {
+            if name == "game" {
+                return Ok(Some(block));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<R: BufRead> Iterator for ClrMameProReader<R> {
+    type Item = DatResult<GameEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(game) = self.pending.take() {
+            return Some((self.parse_game)(game));
+        }
+        if self.done {
+            return None;
+        }
+        match self.read_game() {
+            Ok(Some(game)) => Some((self.parse_game)(game)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err.into()))
+            }
+        }
+    }
+}
+
+/// The keys of the `clrmamepro` block that are ClrMamePro options.
+const CLRMAMEPRO_OPTIONS: &[&str] = &["header", "forcemerging", "forcenodump", "forcepacking"];
+
+/// Moves the ClrMamePro options of the `clrmamepro` block under a `clrmamepro` key,
+/// as they are nested in the header of a Logiqx XML DAT.
+fn nest_header_options(block: Node) -> Node {
+    match block {
+        Node::List(entries) => {
+            let (options, mut entries): (Vec<_>, Vec<_>) = entries
+                .into_iter()
+                .partition(|(key, _)| CLRMAMEPRO_OPTIONS.contains(&key.as_str()));
+            if !options.is_empty() {
+                entries.push(("clrmamepro".to_string(), Node::List(options)));
+            }
+            Node::List(entries)
+        }
+        value => value,
+    }
+}
+
+/// The values of the `flags` key of a `rom` that are the dump status of the ROM.
+const ROM_STATUS_FLAGS: &[&str] = &["nodump", "baddump", "verified"];
+
+/// Moves the dump status of each `rom` of a `game` block from its `flags` key to a `status` key,
+/// as it is an attribute of the `rom` element in a Logiqx XML DAT.
+fn rom_flags_as_status(game: Node) -> Node {
+    match game {
+        Node::List(entries) => Node::List(
+            entries
+                .into_iter()
+                .map(|(key, value)| match (key.as_str(), value) {
+                    ("rom", Node::List(mut rom)) => {
+                        let has_status = rom.iter().any(|(k, _)| k == "status");
+                        let status = rom.iter().find_map(|(k, v)| match v {
+                            Node::Value(flag) if k == "flags" && ROM_STATUS_FLAGS.contains(&flag.as_str()) => {
+                                Some(flag.clone())
+                            }
+                            _ => None,
+                        });
+                        if let (false, Some(status)) = (has_status, status) {
+                            rom.push(("status".to_string(), Node::Value(status)));
+                        }
+                        (key, Node::List(rom))
+                    }
+                    (_, value) => (key, value),
+                })
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+fn parse_game<G: DeserializeOwned + TryInto<GameEntry, Error = DatError>>(game: Node) -> DatResult<GameEntry> {
+    let game = G::deserialize(Field(vec![rom_flags_as_status(game)]))?;
+    game.try_into()
+}
+
+/// Deserializes the values of all occurrences of a key in a list.
+///
+/// Keys that occur more than once, such as `rom`, deserialize as a sequence,
+/// otherwise the first value is used.
+pub(crate) struct Field(pub(crate) Vec<Node>);
+
+impl Field {
+    fn into_value(self) -> Result<String> {
+        match self.0.into_iter().next() {
+            Some(Node::Value(value)) => Ok(value),
+            Some(Node::List(_)) => Err(ClrMameProError("Expected a value, found a list".to_string())),
+            None => Err(ClrMameProError("Expected a value".to_string())),
+        }
+    }
+}
+
+/// Groups the pairs of a list by key, keeping the order each key first appears in.
+fn group_by_key(entries: Vec<(String, Node)>) -> Vec<(String, Field)> {
+    let mut fields: Vec<(String, Field)> = Vec::new();
+    for (key, value) in entries {
+        match fields.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, field)) => field.0.push(value),
+            None => fields.push((key, Field(vec![value]))),
+        }
+    }
+    fields
+}
+
+impl<'de> IntoDeserializer<'de, ClrMameProError> for Field {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method: ident => $visit: ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                let value = self.into_value()?;
+                visitor.$visit(value.parse().map_err(|_| ClrMameProError(format!("Invalid value {}", value)))?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Field {
+    type Error = ClrMameProError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.0.len() > 1 {
+            return self.deserialize_seq(visitor);
+        }
+        match self.0.into_iter().next() {
+            Some(Node::Value(value)) => visitor.visit_string(value),
+            Some(Node::List(entries)) => {
+                let mut map = MapDeserializer::new(group_by_key(entries).into_iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+            None => visitor.visit_unit(),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let mut seq = SeqDeserializer::new(self.0.into_iter().map(|node| Field(vec![node])));
+        let value = visitor.visit_seq(&mut seq)?;
+        seq.end()?;
+        Ok(value)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit unit_struct newtype_struct
+        tuple tuple_struct map struct enum identifier
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nointro::FromNoIntro;
+    use crate::redump::FromRedump;
+    use crate::generic::FromGeneric;
+    use crate::{DatError, GameEntry, RomStatus};
+
+    const NOINTRO_DAT: &str = r#"clrmamepro (
+	name "Nintendo - Game Boy"
+	description "Nintendo - Game Boy"
+	version 20200101-000000
+	homepage No-Intro
+)
+
+game (
+	name "Tetris (World) (Rev A)"
+	description "Tetris (World) (Rev A)"
+	rom ( name "Tetris (World) (Rev A).gb" size 32768 crc 46DF91AD md5 982ED5D2B12A0377EB14BCDC4123744E sha1 74591CC9501AF93873F9A5D3EB12DA12C0723BBC )
+)
+
+game (
+	name "Pokemon - Blue Version (USA, Europe) (SGB Enhanced)"
+	description "Pokemon - Blue Version (USA, Europe) (SGB Enhanced)"
+	rom ( name "Pokemon - Blue Version (USA, Europe) (SGB Enhanced).gb" size 1048576 crc D6DA8A1A md5 50927E843568814F7ED45EC4F944BD8B sha1 D7037C83E1AE5B39BDE3C30787637BA1D4C48CE2 serial "DMG-APEE-USA, DMG-APEP-EUR" )
+)
+"#;
+
+    #[test]
+    fn parses_clrmamepro_dat() {
+        let entries: Vec<GameEntry> = GameEntry::try_from_nointro_str(NOINTRO_DAT)
+            .unwrap()
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(2, entries.len());
+        assert_eq!("Tetris (World) (Rev A)", entries[0].entry_name());
+        assert_eq!("Tetris", entries[0].info().unwrap().entry_title());
+        assert_eq!("Tetris (World) (Rev A).gb", entries[0].rom_entries()[0].file_name());
+        assert_eq!(32768, entries[0].rom_entries()[0].size());
+        assert_eq!("46df91ad", entries[0].rom_entries()[0].hash_crc().unwrap().to_string());
+        assert_eq!(2, entries[1].serials().len());
+        assert_eq!("DMG-APEP-EUR", entries[1].serials()[1].as_ref());
+    }
+
+    #[test]
+    fn parses_clrmamepro_header() {
+        let dat = GameEntry::try_from_nointro_str(NOINTRO_DAT).unwrap();
+        let header = dat.header().unwrap();
+        assert_eq!(Some("Nintendo - Game Boy"), header.name());
+        assert_eq!(Some("20200101-000000"), header.version());
+        assert_eq!(Some("No-Intro"), header.homepage());
+        assert_eq!(None, header.clrmamepro());
+
+        let dat = GameEntry::try_from_str("clrmamepro ( name Test forcenodump required )").unwrap();
+        let header = dat.header().unwrap();
+        assert_eq!(Some("Test"), header.name());
+        assert_eq!(Some("required"), header.clrmamepro().unwrap().force_nodump());
+    }
+
+    #[test]
+    fn checks_clrmamepro_header() {
+        match GameEntry::try_from_redump_str(NOINTRO_DAT) {
+            Err(DatError::HeaderMismatchError("redump.org", Some(homepage))) => assert_eq!("No-Intro", homepage),
+            _ => panic!("expected header mismatch"),
+        }
+    }
+
+    #[test]
+    fn reads_multiple_roms() {
+        let dat = r#"clrmamepro ( name "Sony - PlayStation" homepage redump.org )
+game (
+	name "Example Game (USA)"
+	serial "SLUS-00001"
+	rom ( name "Example Game (USA) (Track 1).bin" size 1024 crc 00000001 md5 00000000000000000000000000000001 sha1 0000000000000000000000000000000000000001 )
+	rom ( name "Example Game (USA) (Track 2).bin" size 2048 crc 00000002 md5 00000000000000000000000000000002 sha1 0000000000000000000000000000000000000002 )
+)
+"#;
+        let dat = GameEntry::try_from_redump_str(dat).unwrap();
+        let entry = dat.entries()[0].as_ref().unwrap();
+        assert_eq!(2, entry.rom_entries().len());
+        assert_eq!(2048, entry.rom_entries()[1].size());
+        assert_eq!("SLUS-00001", entry.serials()[0].as_ref());
+    }
+
+    #[test]
+    fn reads_rom_flags_as_status() {
+        let dat = r#"clrmamepro ( name "Test" )
+game (
+	name "Example Game"
+	rom ( name "good.bin" size 1024 crc 00000001 )
+	rom ( name "missing.bin" size 2048 flags nodump )
+	rom ( name "bad.bin" size 1024 crc 00000003 flags baddump )
+	rom ( name "verified.bin" size 1024 crc 00000004 flags verified )
+)
+"#;
+        let dat = GameEntry::try_from_str(dat).unwrap();
+        let entry = dat.entries()[0].as_ref().unwrap();
+        let statuses: Vec<RomStatus> = entry.rom_entries().iter().map(|rom| rom.status()).collect();
+        assert_eq!(
+            vec![RomStatus::Good, RomStatus::NoDump, RomStatus::BadDump, RomStatus::Verified],
+            statuses
+        );
+        assert_eq!(None, entry.rom_entries()[1].hash_crc());
+    }
+
+    #[test]
+    fn unterminated_block_is_an_error() {
+        let dat = "clrmamepro ( homepage No-Intro )\ngame ( name \"Tetris (World)\" rom ( name \"Tetris (World).gb\"";
+        let dat = GameEntry::try_from_nointro_str(dat).unwrap();
+        assert_eq!(1, dat.entries().len());
+        assert!(dat.entries()[0].is_err());
+    }
+}
@@ -0,0 +1,145 @@
+use serde::Deserialize;
+
+/// The header of a DAT, describing the DAT file itself.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Deserialize)]
+pub struct DatHeader {
+    /// The name of the DAT.
+    pub(in super::super) name: Option<String>,
+    /// The description of the DAT.
+    pub(in super::super) description: Option<String>,
+    /// The version of the DAT.
+    pub(in super::super) version: Option<String>,
+    /// The date the DAT was published.
+    pub(in super::super) date: Option<String>,
+    /// The author of the DAT.
+    pub(in super::super) author: Option<String>,
+    /// The homepage of the cataloguing organization that published the DAT.
+    pub(in super::super) homepage: Option<String>,
+    /// The URL of the DAT.
+    pub(in super::super) url: Option<String>,
+    /// Any comment left in the DAT.
+    pub(in super::super) comment: Option<String>,
+    /// Options for ClrMamePro.
+    pub(in super::super) clrmamepro: Option<ClrMameProOptions>,
+    /// Options for RomCenter.
+    pub(in super::super) romcenter: Option<RomCenterOptions>,
+}
+
+/// The header of a DAT, describing the DAT file itself.
+impl DatHeader {
+    /// The name of the DAT.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+    /// The description of the DAT.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+    /// The version of the DAT.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+    /// The date the DAT was published.
+    pub fn date(&self) -> Option<&str> {
+        self.date.as_deref()
+    }
+    /// The author of the DAT.
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+    /// The homepage of the cataloguing organization that published the DAT.
+    pub fn homepage(&self) -> Option<&str> {
+        self.homepage.as_deref()
+    }
+    /// The URL of the DAT.
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+    /// Any comment left in the DAT.
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+    /// Options for ClrMamePro.
+    pub fn clrmamepro(&self) -> Option<&ClrMameProOptions> {
+        self.clrmamepro.as_ref()
+    }
+    /// Options for RomCenter.
+    pub fn romcenter(&self) -> Option<&RomCenterOptions> {
+        self.romcenter.as_ref()
+    }
+    /// A copy of this header with the given name and description,
+    /// such as for a DAT derived from this one.
+    pub fn renamed(&self, name: &str, description: &str) -> DatHeader {
+        DatHeader {
+            name: Some(name.to_string()),
+            description: Some(description.to_string()),
+            ..self.clone()
+        }
+    }
+}
+
+/// Options for ClrMamePro, from the `clrmamepro` element of the header.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Deserialize)]
+pub struct ClrMameProOptions {
+    /// The name of the header skipper detector to use.
+    pub(in super::super) header: Option<String>,
+    /// How sets should be merged, one of `none`, `split`, or `full`.
+    pub(in super::super) forcemerging: Option<String>,
+    /// How ROMs without dumps should be treated, one of `obsolete`, `required`, or `ignore`.
+    pub(in super::super) forcenodump: Option<String>,
+    /// How sets should be packed, one of `zip` or `unzip`.
+    pub(in super::super) forcepacking: Option<String>,
+}
+
+/// Options for ClrMamePro, from the `clrmamepro` element of the header.
+impl ClrMameProOptions {
+    /// The name of the header skipper detector to use.
+    pub fn header(&self) -> Option<&str> {
+        self.header.as_deref()
+    }
+    /// How sets should be merged, one of `none`, `split`, or `full`.
+    pub fn force_merging(&self) -> Option<&str> {
+        self.forcemerging.as_deref()
+    }
+    /// How ROMs without dumps should be treated, one of `obsolete`, `required`, or `ignore`.
+    pub fn force_nodump(&self) -> Option<&str> {
+        self.forcenodump.as_deref()
+    }
+    /// How sets should be packed, one of `zip` or `unzip`.
+    pub fn force_packing(&self) -> Option<&str> {
+        self.forcepacking.as_deref()
+    }
+}
+
+/// Options for RomCenter, from the `romcenter` element of the header.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Deserialize)]
+pub struct RomCenterOptions {
+    /// The RomCenter plugin to use.
+    pub(in super::super) plugin: Option<String>,
+    /// How ROM sets are stored, one of `merged`, `split`, or `unmerged`.
+    pub(in super::super) rommode: Option<String>,
+    /// How BIOS sets are stored, one of `merged`, `split`, or `unmerged`.
+    pub(in super::super) biosmode: Option<String>,
+    /// How sample sets are stored, one of `merged` or `unmerged`.
+    pub(in super::super) samplemode: Option<String>,
+}
+
+/// Options for RomCenter, from the `romcenter` element of the header.
+impl RomCenterOptions {
+    /// The RomCenter plugin to use.
+    pub fn plugin(&self) -> Option<&str> {
+        self.plugin.as_deref()
+    }
+    /// How ROM sets are stored, one of `merged`, `split`, or `unmerged`.
+    pub fn rom_mode(&self) -> Option<&str> {
+        self.rommode.as_deref()
+    }
+    /// How BIOS sets are stored, one of `merged`, `split`, or `unmerged`.
+    pub fn bios_mode(&self) -> Option<&str> {
+        self.biosmode.as_deref()
+    }
+    /// How sample sets are stored, one of `merged` or `unmerged`.
+    pub fn sample_mode(&self) -> Option<&str> {
+        self.samplemode.as_deref()
+    }
+}
@@ -14,6 +14,17 @@ impl fmt::Display for NameInfo {
                 .map(|r| r.into())
                 .collect::<Vec<&str>>()
         )?;
+        writeln!(f, "    (languages {:?})", self.languages())?;
+        writeln!(f, "    (date \"{}\")", self.release_date().unwrap_or("None"))?;
+        writeln!(f, "    (publishers {:?})", self.publishers())?;
+        writeln!(
+            f,
+            "    (dump {:?})",
+            self.dump_quality()
+                .iter()
+                .map(|q| q.as_ref())
+                .collect::<Vec<&str>>()
+        )?;
         writeln!(f, "    (part {})", self.part_number().map(|i| format!("{}", i)).as_deref().unwrap_or("None"))?;
         writeln!(f, "    (version \"{}\")", self.version().unwrap_or("None"))?;
         writeln!(f, "    (status {:?})", self.development_status())?;
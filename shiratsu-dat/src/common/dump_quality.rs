@@ -0,0 +1,48 @@
+/// A flag in the name of a game entry that describes the quality or provenance of the dump.
+///
+/// Only TOSEC and GoodTools names carry dump flags.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum DumpQuality {
+    /// A bad dump, with incorrect or missing data. In TOSEC, this is the `[b]` and `[u]` flags.
+    /// In GoodTools, this is the `[b]` flag.
+    BadDump,
+    /// An alternate version of an otherwise identical dump, from the `[a]` flag.
+    Alternate,
+    /// A dump that was modified to change its content, from the `[h]` flag,
+    /// or the GoodTools `(Hack)` flag.
+    Hack,
+    /// A dump with a trainer or cheat added, from the `[t]` flag.
+    Trainer,
+    /// A dump with copy protection removed, from the TOSEC `[cr]` flag.
+    Cracked,
+    /// A dump that was modified to run properly, from the `[f]` flag.
+    Fixed,
+    /// A pirated release of the game, from the `[p]` flag.
+    Pirate,
+    /// A fan translation of the game, from the TOSEC `[tr]` flag or the GoodTools `[T+]` and `[T-]` flags.
+    Translation,
+    /// A dump that is verified to be good, from the `[!]` flag.
+    Verified,
+}
+
+impl From<&DumpQuality> for &str {
+    fn from(quality: &DumpQuality) -> Self {
+        match quality {
+            DumpQuality::BadDump => "bad",
+            DumpQuality::Alternate => "alternate",
+            DumpQuality::Hack => "hack",
+            DumpQuality::Trainer => "trainer",
+            DumpQuality::Cracked => "cracked",
+            DumpQuality::Fixed => "fixed",
+            DumpQuality::Pirate => "pirate",
+            DumpQuality::Translation => "translation",
+            DumpQuality::Verified => "verified",
+        }
+    }
+}
+
+impl AsRef<str> for DumpQuality {
+    fn as_ref(&self) -> &str {
+        self.into()
+    }
+}
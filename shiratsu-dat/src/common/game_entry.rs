@@ -1,7 +1,7 @@
 use crate::{NameInfo, RomEntry, Serial};
 
 /// A single entry that describes a game, which may hold a collection of RomEntries
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct GameEntry {
     /// The name of the game entry, as is.
     pub(in super::super) entry_name: String,
@@ -13,6 +13,10 @@ pub struct GameEntry {
     pub(in super::super) source: &'static str,
     /// Any information retrieved from the name of the game entry, if any.
     pub(in super::super) info: Option<NameInfo>,
+    /// The name of the game entry this game entry is a clone of, if any.
+    pub(in super::super) parent_name: Option<String>,
+    /// The name of the game entry this game entry shares ROMs with, if any.
+    pub(in super::super) rom_of: Option<String>,
 }
 
 /// A single entry that describes a game, which may hold a collection of RomEntries
@@ -36,4 +40,12 @@ impl GameEntry {
     pub fn info(&self) -> Option<&NameInfo> {
         self.info.as_ref()
     }
+    /// The name of the game entry this game entry is a clone of, if any.
+    pub fn parent_name(&self) -> Option<&str> {
+        self.parent_name.as_deref()
+    }
+    /// The name of the game entry this game entry shares ROMs with, if any.
+    pub fn rom_of(&self) -> Option<&str> {
+        self.rom_of.as_deref()
+    }
 }
\ No newline at end of file
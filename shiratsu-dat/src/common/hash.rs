@@ -0,0 +1,117 @@
+use crate::error::DatError;
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+macro_rules! hash_type {
+    ($(#[$meta:meta])* $name:ident, $len:literal, $kind:literal) => {
+        $(#[$meta])*
+        #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+        pub struct $name([u8; $len]);
+
+        impl $name {
+            /// The raw bytes of the hash.
+            pub fn as_bytes(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl From<[u8; $len]> for $name {
+            fn from(bytes: [u8; $len]) -> Self {
+                $name(bytes)
+            }
+        }
+
+        /// Parses the hash from a hex string of either case.
+        impl FromStr for $name {
+            type Err = DatError;
+            fn from_str(hash: &str) -> Result<Self, Self::Err> {
+                let mut bytes = [0u8; $len];
+                if !decode_hex(hash, &mut bytes) {
+                    return Err(DatError::ParseError(format!(
+                        "{} is not a valid {} hash, expected {} hex digits",
+                        hash,
+                        $kind,
+                        $len * 2
+                    )));
+                }
+                Ok($name(bytes))
+            }
+        }
+
+        /// Formats the hash as a lowercase hex string.
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                for byte in self.0.iter() {
+                    write!(f, "{:02x}", byte)?;
+                }
+                Ok(())
+            }
+        }
+    };
+}
+
+hash_type! {
+    /// A CRC32 hash.
+    Crc32, 4, "CRC32"
+}
+
+hash_type! {
+    /// An MD5 hash.
+    Md5, 16, "MD5"
+}
+
+hash_type! {
+    /// A SHA1 hash.
+    Sha1, 20, "SHA1"
+}
+
+hash_type! {
+    /// A SHA256 hash.
+    Sha256, 32, "SHA256"
+}
+
+hash_type! {
+    /// A 64-bit XXH3 hash.
+    Xxh3, 8, "XXH3"
+}
+
+/// Decodes the hex string into the buffer, returning false if it is not exactly as long as the buffer.
+fn decode_hex(hex: &str, buf: &mut [u8]) -> bool {
+    if hex.len() != buf.len() * 2 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return false;
+    }
+    for (byte, i) in buf.iter_mut().zip((0..hex.len()).step_by(2)) {
+        match u8::from_str_radix(&hex[i..i + 2], 16) {
+            Ok(value) => *byte = value,
+            Err(_) => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_formats_hashes() {
+        let crc: Crc32 = "46DF91AD".parse().unwrap();
+        assert_eq!(&[0x46, 0xdf, 0x91, 0xad], crc.as_bytes());
+        assert_eq!("46df91ad", crc.to_string());
+        assert_eq!(crc, "46df91ad".parse().unwrap());
+
+        let sha1: Sha1 = "74591CC9501AF93873F9A5D3EB12DA12C0723BBC".parse().unwrap();
+        assert_eq!("74591cc9501af93873f9a5d3eb12da12c0723bbc", sha1.to_string());
+    }
+
+    #[test]
+    fn rejects_malformed_hashes() {
+        assert!("46DF91A".parse::<Crc32>().is_err());
+        assert!("46DF91ADAD".parse::<Crc32>().is_err());
+        assert!("46DF91AG".parse::<Crc32>().is_err());
+        assert!("+6DF91AD".parse::<Crc32>().is_err());
+        assert!("46DF91AD".parse::<Md5>().is_err());
+        assert!("ÿÿÿÿ".parse::<Crc32>().is_err());
+    }
+}
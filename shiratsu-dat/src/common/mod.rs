@@ -2,16 +2,24 @@
 mod util;
 
 mod serial;
+mod hash;
 mod rom_entry;
+mod rom_status;
 mod game_entry;
+mod dat_header;
 
 mod name_info;
 mod development_status;
+mod dump_quality;
 mod display;
 
 pub use name_info::*;
 pub use development_status::DevelopmentStatus;
+pub use dump_quality::DumpQuality;
 pub use display::*;
 pub use game_entry::*;
+pub use dat_header::*;
 pub use rom_entry::*;
+pub use rom_status::RomStatus;
 pub use serial::*;
+pub use hash::{Crc32, Md5, Sha1, Sha256, Xxh3};
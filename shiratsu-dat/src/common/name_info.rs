@@ -1,5 +1,5 @@
 use shiratsu_naming::region::Region;
-use crate::DevelopmentStatus;
+use crate::{DevelopmentStatus, DumpQuality};
 use shiratsu_naming::naming::{NamingConvention, FlagType, TokenizedName};
 use shiratsu_naming::naming::nointro::*;
 use crate::common::util::{move_default_articles_mut, replace_hyphen_mut};
@@ -11,6 +11,11 @@ pub struct NameInfo {
     pub entry_title: String,
     pub release_title: String,
     pub region: Vec<Region>,
+    pub languages: Vec<String>,
+    pub release_year: Option<i32>,
+    pub release_date: Option<String>,
+    pub publishers: Vec<String>,
+    pub dump_quality: Vec<DumpQuality>,
     pub part_number: Option<i32>,
     pub version: Option<String>,
     pub is_unlicensed: bool,
@@ -25,6 +30,30 @@ impl NameInfo {
     pub fn region(&self) -> &[Region] {
         &self.region
     }
+    /// The languages of the game as lowercase ISO 639-1 codes, in the order they appear.
+    ///
+    /// This is empty if the name does not list its languages.
+    pub fn languages(&self) -> &[String] {
+        &self.languages
+    }
+    /// The year the game was released, if the name has a year with no unknown digits.
+    pub fn release_year(&self) -> Option<i32> {
+        self.release_year
+    }
+    /// The date the game was released, in the form `YYYY`, `YYYY-MM` or `YYYY-MM-DD`.
+    ///
+    /// Unknown digits are replaced with `x`, such as in `19xx` or `1997-xx`.
+    pub fn release_date(&self) -> Option<&str> {
+        self.release_date.as_deref()
+    }
+    /// The publishers of the game, in the order they appear.
+    pub fn publishers(&self) -> &[String] {
+        &self.publishers
+    }
+    /// The dump flags of the game entry, in the order they appear, without duplicates.
+    pub fn dump_quality(&self) -> &[DumpQuality] {
+        &self.dump_quality
+    }
     /// If this entry is split into multiple parts, the part number of this entry.
     pub fn part_number(&self) -> Option<i32> {
         self.part_number
@@ -77,6 +106,35 @@ impl<'a, T> From<T> for NameInfo
     }
 }
 
+/// Adds a language code as a lowercase ISO 639-1 code, ignoring any script or variant subtag.
+fn push_language(languages: &mut Vec<String>, code: &str) {
+    if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return;
+    }
+    let code = code.to_ascii_lowercase();
+    if !languages.contains(&code) {
+        languages.push(code)
+    }
+}
+
+/// Sets the release date from its parts, and the release year if the year is fully known.
+fn set_release_date(name: &mut NameInfo, year: &str, month: Option<&str>, day: Option<&str>) {
+    let mut date = year.to_ascii_lowercase();
+    for part in month.iter().chain(day.iter()) {
+        date.push('-');
+        date.push_str(&part.to_ascii_lowercase());
+    }
+    name.release_year = year.parse::<i32>().ok();
+    name.release_date = Some(date);
+}
+
+/// Adds a dump flag, if the game entry does not already have it.
+fn push_dump_quality(name: &mut NameInfo, quality: DumpQuality) {
+    if !name.dump_quality.contains(&quality) {
+        name.dump_quality.push(quality)
+    }
+}
+
 impl <'a> ToNameInfo for NoIntroName<'a>
 {
     fn to_name_info(&self) -> NameInfo {
@@ -84,6 +142,11 @@ impl <'a> ToNameInfo for NoIntroName<'a>
             entry_title: "".to_string(),
             release_title: "".to_string(),
             region: vec![Region::Unknown],
+            languages: Vec::new(),
+            release_year: None,
+            release_date: None,
+            publishers: Vec::new(),
+            dump_quality: Vec::new(),
             part_number: None,
             version: None,
             is_unlicensed: false,
@@ -119,6 +182,11 @@ impl <'a> ToNameInfo for NoIntroName<'a>
                 }
                 NoIntroToken::Media(_, part) => { name.part_number = part.parse::<i32>().ok() }
                 NoIntroToken::Region(_, region) => { name.region = region.clone() }
+                NoIntroToken::Languages(languages) => {
+                    for (code, _) in languages.iter() {
+                        push_language(&mut name.languages, code)
+                    }
+                }
                 NoIntroToken::Flag(_, "BIOS") => { name.is_system = true }
                 _ => {}
             }
@@ -141,6 +209,11 @@ impl <'a> ToNameInfo for GoodToolsName<'a>
             entry_title: "".to_string(),
             release_title: "".to_string(),
             region: vec![Region::Unknown],
+            languages: Vec::new(),
+            release_year: None,
+            release_date: None,
+            publishers: Vec::new(),
+            dump_quality: Vec::new(),
             part_number: None,
             version: None,
             is_unlicensed: false,
@@ -153,6 +226,26 @@ impl <'a> ToNameInfo for GoodToolsName<'a>
             match token {
                 GoodToolsToken::Title(t) => name.entry_title = t.to_string(),
                 GoodToolsToken::Region(_, region) => name.region = region.clone(),
+                GoodToolsToken::Year(year) => set_release_date(&mut name, year, None, None),
+                GoodToolsToken::DumpCode(code, ..) => {
+                    let quality = match *code {
+                        "b" => Some(DumpQuality::BadDump),
+                        "a" => Some(DumpQuality::Alternate),
+                        "h" => Some(DumpQuality::Hack),
+                        "t" => Some(DumpQuality::Trainer),
+                        "f" | "f_" => Some(DumpQuality::Fixed),
+                        "p" => Some(DumpQuality::Pirate),
+                        "!" => Some(DumpQuality::Verified),
+                        _ => None,
+                    };
+                    if let Some(quality) = quality {
+                        push_dump_quality(&mut name, quality)
+                    }
+                }
+                GoodToolsToken::GameHack(_) => push_dump_quality(&mut name, DumpQuality::Hack),
+                GoodToolsToken::Translation(..) => push_dump_quality(&mut name, DumpQuality::Translation),
+                // (M#) only tells how many languages there are, not which.
+                GoodToolsToken::MultiLanguage(_) => {}
                 GoodToolsToken::Version(_, major, Some(minor)) =>
                     name.version = Some(format!("{}.{}", major, minor)),
                 GoodToolsToken::Version(_, major, _) =>
@@ -189,6 +282,11 @@ impl <'a> ToNameInfo for TOSECName<'a>
             entry_title: "".to_string(),
             release_title: "".to_string(),
             region: vec![Region::Unknown],
+            languages: Vec::new(),
+            release_year: None,
+            release_date: None,
+            publishers: Vec::new(),
+            dump_quality: Vec::new(),
             part_number: None,
             version: None,
             is_unlicensed: false,
@@ -207,6 +305,19 @@ impl <'a> ToNameInfo for TOSECName<'a>
                 TOSECToken::Region(_, regions) => {
                     name.region = regions.clone()
                 }
+                TOSECToken::Date(year, month, day) => {
+                    set_release_date(&mut name, year, *month, *day)
+                }
+                TOSECToken::Publisher(Some(publishers)) => {
+                    name.publishers = publishers.iter().map(|p| p.to_string()).collect()
+                }
+                TOSECToken::Languages(TOSECLanguage::Single(code)) => {
+                    push_language(&mut name.languages, code)
+                }
+                TOSECToken::Languages(TOSECLanguage::Double(first, second)) => {
+                    push_language(&mut name.languages, first);
+                    push_language(&mut name.languages, second)
+                }
                 TOSECToken::Media(parts) => {
                     if let Some(parts) = parts.first()
                     {
@@ -228,8 +339,25 @@ impl <'a> ToNameInfo for TOSECName<'a>
                         Some(minor) => { name.version = Some(format!("{}.{}", major, minor)) }
                     }
                 }
-                TOSECToken::DumpInfo("p", _, _) => {
-                    name.is_unlicensed = true
+                TOSECToken::DumpInfo(code, _, _) => {
+                    let quality = match *code {
+                        "b" | "u" => Some(DumpQuality::BadDump),
+                        "a" => Some(DumpQuality::Alternate),
+                        "h" => Some(DumpQuality::Hack),
+                        "t" => Some(DumpQuality::Trainer),
+                        "cr" => Some(DumpQuality::Cracked),
+                        "f" => Some(DumpQuality::Fixed),
+                        "p" => Some(DumpQuality::Pirate),
+                        "tr" => Some(DumpQuality::Translation),
+                        "!" => Some(DumpQuality::Verified),
+                        _ => None,
+                    };
+                    if let Some(quality) = quality {
+                        push_dump_quality(&mut name, quality)
+                    }
+                    if *code == "p" {
+                        name.is_unlicensed = true
+                    }
                 }
                 TOSECToken::Demo(_) => {
                     name.is_demo = true
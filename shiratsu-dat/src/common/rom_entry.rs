@@ -1,30 +1,82 @@
+use crate::error::{DatError, Result};
+use crate::{Crc32, Md5, RomStatus, Sha1, Sha256, Xxh3};
+
 #[derive(Debug)]
 pub struct RomEntry {
     /// The MD5 Hash of the ROM
-    pub(in super::super) md5: Option<String>,
+    pub(in super::super) md5: Option<Md5>,
     /// The SHA1 Hash of the ROM
-    pub(in super::super) sha1: Option<String>,
+    pub(in super::super) sha1: Option<Sha1>,
+    /// The SHA256 Hash of the ROM
+    pub(in super::super) sha256: Option<Sha256>,
+    /// The 64-bit XXH3 Hash of the ROM
+    pub(in super::super) xxh3: Option<Xxh3>,
     /// The CRC hash of the ROM
-    pub(in super::super) crc: Option<String>,
+    pub(in super::super) crc: Option<Crc32>,
     /// The canonical file name of the ROM
     pub(in super::super) file_name: String,
     /// The size of the ROM
     pub(in super::super) size: i64,
+    /// The status of the ROM
+    pub(in super::super) status: RomStatus,
 }
 
 /// Describes a single file that is a part of a GameEntry
 impl RomEntry {
+    /// Creates a `RomEntry` from the attributes of a `rom` element.
+    ///
+    /// Fails if any of the hashes are malformed, or if the ROM has none of the CRC, MD5, SHA1,
+    /// SHA256 or XXH3 hashes, unless its status is `nodump`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn from_attributes(
+        file_name: String,
+        size: Option<i64>,
+        crc: Option<String>,
+        md5: Option<String>,
+        sha1: Option<String>,
+        sha256: Option<String>,
+        xxh3: Option<String>,
+        status: Option<&str>,
+    ) -> Result<RomEntry> {
+        let status = RomStatus::from_attribute(status);
+        let has_hashes = crc.is_some() || md5.is_some() || sha1.is_some() || sha256.is_some() || xxh3.is_some();
+        if !has_hashes && status != RomStatus::NoDump {
+            return Err(DatError::ParseError(format!(
+                "ROM {} has no hashes and is not marked nodump",
+                file_name
+            )));
+        }
+        Ok(RomEntry {
+            md5: md5.map(|md5| md5.parse()).transpose()?,
+            sha1: sha1.map(|sha1| sha1.parse()).transpose()?,
+            sha256: sha256.map(|sha256| sha256.parse()).transpose()?,
+            xxh3: xxh3.map(|xxh3| xxh3.parse()).transpose()?,
+            crc: crc.map(|crc| crc.parse()).transpose()?,
+            file_name,
+            size: size.unwrap_or(0),
+            status,
+        })
+    }
+
     /// The MD5 Hash of the ROM
-    pub fn hash_md5(&self) -> Option<&str> {
-        self.md5.as_deref()
+    pub fn hash_md5(&self) -> Option<&Md5> {
+        self.md5.as_ref()
     }
     /// The SHA1 Hash of the ROM
-    pub fn hash_sha1(&self) -> Option<&str> {
-        self.sha1.as_deref()
+    pub fn hash_sha1(&self) -> Option<&Sha1> {
+        self.sha1.as_ref()
+    }
+    /// The SHA256 Hash of the ROM
+    pub fn hash_sha256(&self) -> Option<&Sha256> {
+        self.sha256.as_ref()
+    }
+    /// The 64-bit XXH3 Hash of the ROM
+    pub fn hash_xxh3(&self) -> Option<&Xxh3> {
+        self.xxh3.as_ref()
     }
     /// The CRC hash of the ROM
-    pub fn hash_crc(&self) -> Option<&str> {
-        self.crc.as_deref()
+    pub fn hash_crc(&self) -> Option<&Crc32> {
+        self.crc.as_ref()
     }
     /// The canonical file name of the ROM
     pub fn file_name(&self) -> &str {
@@ -34,22 +86,95 @@ impl RomEntry {
     pub fn size(&self) -> i64 {
         self.size
     }
+    /// The status of the ROM
+    pub fn status(&self) -> RomStatus {
+        self.status
+    }
 }
 
-/// Two RomEntries are PartialEq if they have matching hashes.
+/// Two RomEntries are PartialEq if every hash that both have matches, and they have at least one hash in common.
+///
+/// ROMs without any hash, such as ROMs that were never dumped, are PartialEq if they have the same
+/// file name and status.
 impl PartialEq for RomEntry {
     fn eq(&self, other: &RomEntry) -> bool {
-        if let (Some(my_md5), Some(other_md5),
-                Some(my_sha1), Some(other_sha1),
-                Some(my_crc), Some(other_crc)) = 
-                (self.hash_md5(), other.hash_md5(),
-                self.hash_sha1(), other.hash_sha1(),
-                self.hash_crc(), other.hash_crc()) {
-            my_md5 == other_md5 
-                    && my_sha1 == other_sha1
-                    && my_crc == other_crc
-        } else {
-            false
+        fn compare<T: PartialEq>(a: &Option<T>, b: &Option<T>) -> Option<bool> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a == b),
+                _ => None,
+            }
         }
+        let compared = [
+            compare(&self.crc, &other.crc),
+            compare(&self.md5, &other.md5),
+            compare(&self.sha1, &other.sha1),
+            compare(&self.sha256, &other.sha256),
+            compare(&self.xxh3, &other.xxh3),
+        ];
+        let hashless = |rom: &RomEntry| {
+            rom.crc.is_none() && rom.md5.is_none() && rom.sha1.is_none() && rom.sha256.is_none() && rom.xxh3.is_none()
+        };
+        if hashless(self) && hashless(other) {
+            return self.file_name == other.file_name && self.status == other.status;
+        }
+        compared.iter().any(Option::is_some) && compared.iter().flatten().all(|&eq| eq)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rom(crc: Option<&str>, sha1: Option<&str>) -> RomEntry {
+        RomEntry::from_attributes(
+            "Tetris (World).gb".to_string(),
+            Some(32768),
+            crc.map(String::from),
+            None,
+            sha1.map(String::from),
+            None,
+            None,
+            Some("nodump"),
+        )
+        .unwrap()
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn compares_common_hashes() {
+        let sha1 = Some("74591CC9501AF93873F9A5D3EB12DA12C0723BBC");
+        assert_eq!(rom(Some("46df91ad"), sha1), rom(None, sha1));
+        assert_eq!(rom(Some("46df91ad"), None), rom(Some("46DF91AD"), sha1));
+        assert_ne!(rom(Some("46df91ad"), sha1), rom(Some("00000000"), sha1));
+        assert_ne!(rom(Some("46df91ad"), None), rom(None, sha1));
+        assert_eq!(rom(None, None), rom(None, None));
+        assert_ne!(rom(None, None), rom(None, sha1));
+
+        let renamed = RomEntry::from_attributes(
+            "Tetris (World).sav".to_string(),
+            Some(32768),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some("nodump"),
+        )
+        .unwrap();
+        assert_ne!(rom(None, None), renamed);
+    }
+
+    #[test]
+    fn rejects_malformed_hashes() {
+        let result = RomEntry::from_attributes(
+            "Tetris (World).gb".to_string(),
+            Some(32768),
+            Some("46df91ad0".to_string()),
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        assert!(matches!(result, Err(DatError::ParseError(_))));
+    }
+}
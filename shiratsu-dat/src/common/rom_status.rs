@@ -0,0 +1,41 @@
+/// The status of a ROM, as declared by the `status` attribute of a `rom` element in a DAT.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RomStatus {
+    /// A ROM that is believed to be a good dump. This is the status of a ROM without a `status` attribute.
+    Good,
+    /// A ROM that is known to be a bad dump, but is the best dump available.
+    BadDump,
+    /// A ROM that is known to exist, but has not been dumped. A ROM with this status may have no hashes.
+    NoDump,
+    /// A ROM that has been verified to be a good dump.
+    Verified,
+}
+
+impl RomStatus {
+    /// Parses the `status` attribute of a `rom` element, treating any unknown status as `Good`.
+    pub(crate) fn from_attribute(status: Option<&str>) -> RomStatus {
+        match status {
+            Some("baddump") => RomStatus::BadDump,
+            Some("nodump") => RomStatus::NoDump,
+            Some("verified") => RomStatus::Verified,
+            _ => RomStatus::Good,
+        }
+    }
+}
+
+impl From<&RomStatus> for &str {
+    fn from(status: &RomStatus) -> Self {
+        match status {
+            RomStatus::Good => "good",
+            RomStatus::BadDump => "baddump",
+            RomStatus::NoDump => "nodump",
+            RomStatus::Verified => "verified",
+        }
+    }
+}
+
+impl AsRef<str> for RomStatus {
+    fn as_ref(&self) -> &str {
+        self.into()
+    }
+}
@@ -3,7 +3,7 @@ use regex::Regex;
 use lazy_static::lazy_static;
 use shiratsu_stone::PlatformId;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 #[repr(transparent)]
 pub struct Serial(String);
 
@@ -42,40 +42,31 @@ macro_rules! wrap_error {
 macro_rules! make_parse {
     ($hp: literal, $game: ty, $error: ty) => {
 
-        use crate::xml::*;
+        use crate::reader::*;
         use crate::*;
 
-        fn parse(f: &str) -> Result<Vec<Result<GameEntry>>> {
-            Ok(parse_dat::<$game, $error>(f, Some($hp))?
-                .game
-                .into_iter()
-                .map(|g| g.try_into())
-                .collect())
+        fn parse(f: &str) -> Result<ParsedDat> {
+            Ok(parse_iter(f.as_bytes())?.into())
         }
 
-        fn parse_unchecked(f: &str) -> Result<Vec<Result<GameEntry>>> {
-            Ok(parse_dat_unchecked::<$game, $error>(f)?
-                .game
-                .into_iter()
-                .map(|g| g.try_into())
-                .collect())
+        fn parse_unchecked(f: &str) -> Result<ParsedDat> {
+            Ok(parse_unchecked_iter(f.as_bytes())?.into())
         }
 
-        fn parse_buf<R: std::io::BufRead>(f: R) -> Result<Vec<Result<GameEntry>>> {
-            Ok(
-                parse_dat_buf::<R, $game, $error>(f, Some($hp))?
-                    .game
-                    .into_iter()
-                    .map(|g| g.try_into())
-                    .collect(),
-            )
+        fn parse_buf<R: std::io::BufRead>(f: R) -> Result<ParsedDat> {
+            Ok(parse_iter(f)?.into())
         }
-        fn parse_unchecked_buf<R: std::io::BufRead>(f: R) -> Result<Vec<Result<GameEntry>>> {
-            Ok(parse_dat_unchecked_buf::<R, $game, $error>(f)?
-                .game
-                .into_iter()
-                .map(|g| g.try_into())
-                .collect())
+
+        fn parse_unchecked_buf<R: std::io::BufRead>(f: R) -> Result<ParsedDat> {
+            Ok(parse_unchecked_iter(f)?.into())
+        }
+
+        fn parse_iter<R: std::io::BufRead>(f: R) -> Result<DatReader<R>> {
+            DatReader::new::<$game, $error>(f, Some($hp))
+        }
+
+        fn parse_unchecked_iter<R: std::io::BufRead>(f: R) -> Result<DatReader<R>> {
+            DatReader::new::<$game, $error>(f, None)
         }
     }
 }
@@ -84,10 +75,10 @@ macro_rules! make_from {
     ($hp: expr, $url: expr, $upper: ident, $lower: ident) => {
         use paste::paste;
 
-        use crate::GameEntry;
+        use crate::{GameEntry, ParsedDat};
 
         paste! {
-            /// Provides methods that parse XML .dat files from [
+            /// Provides methods that parse Logiqx XML, ClrMamePro, or RomCenter .dat files from [
             #[doc=$hp]
             ///](
             #[doc=$url]
@@ -95,52 +86,77 @@ macro_rules! make_from {
             pub trait [<From $upper>] {
                 /// Parses the contents of a
                 #[doc=$hp]
-                /// XML DAT into a vector of `GameEntries`.
+                /// DAT into its header and a vector of `GameEntries`.
                 /// This function will check that the
-                /// XML has the proper header for
+                /// DAT has the proper header for
                 #[doc=$hp]
                 /// DATs. Use the unchecked variant if you wish to ignore the header.
-                fn [<try_from_ $lower _str>](dat: &str) -> Result<Vec<Result<GameEntry>>>;
+                fn [<try_from_ $lower _str>](dat: &str) -> Result<ParsedDat>;
 
                 /// Parses the contents of a
                 #[doc=$hp]
-                /// XML DAT into a vector of `GameEntries`,
+                /// DAT into its header and a vector of `GameEntries`,
                 /// ignoring the header element.
-                fn [<try_unchecked_from_ $lower _str>](dat: &str) -> Result<Vec<Result<GameEntry>>>;
+                fn [<try_unchecked_from_ $lower _str>](dat: &str) -> Result<ParsedDat>;
 
                 /// Parses the contents of a
                 #[doc=$hp]
-                /// XML DAT into a vector of `GameEntries`.
+                /// DAT into its header and a vector of `GameEntries`.
                 /// This function will check that the
-                /// XML has the proper header for
+                /// DAT has the proper header for
                 #[doc=$hp]
                 /// DATs. Use the unchecked variant if you wish to ignore the header.
-                fn [<try_from_ $lower _buf>]<R: std::io::BufRead>(buf: R) -> Result<Vec<Result<GameEntry>>>;
+                fn [<try_from_ $lower _buf>]<R: std::io::BufRead>(buf: R) -> Result<ParsedDat>;
 
                 /// Parses the contents of a
                 #[doc=$hp]
-                /// XML DAT into a vector of `GameEntries`,
+                /// DAT into its header and a vector of `GameEntries`,
                 /// ignoring the header element
                 fn [<try_unchecked_from_ $lower _buf>]<R: std::io::BufRead>(
                     buf: R,
-                ) -> Result<Vec<Result<GameEntry>>>;
+                ) -> Result<ParsedDat>;
+
+                /// Streams the contents of a
+                #[doc=$hp]
+                /// DAT, yielding `GameEntries` as they are read.
+                /// This function will check that the
+                /// DAT has the proper header for
+                #[doc=$hp]
+                /// DATs before any entries are read. Use the unchecked variant if you wish to ignore the header.
+                fn [<try_iter_from_ $lower _buf>]<R: std::io::BufRead>(buf: R) -> Result<DatReader<R>>;
+
+                /// Streams the contents of a
+                #[doc=$hp]
+                /// DAT, yielding `GameEntries` as they are read,
+                /// ignoring the header element.
+                fn [<try_unchecked_iter_from_ $lower _buf>]<R: std::io::BufRead>(
+                    buf: R,
+                ) -> Result<DatReader<R>>;
             }
 
             impl [<From $upper>] for GameEntry {
-                fn [<try_from_ $lower _str>](dat: &str) -> Result<Vec<Result<GameEntry>>> {
+                fn [<try_from_ $lower _str>](dat: &str) -> Result<ParsedDat> {
                     parse(dat)
                 }
-                fn [<try_unchecked_from_ $lower _str>](dat: &str) -> Result<Vec<Result<GameEntry>>> {
+                fn [<try_unchecked_from_ $lower _str>](dat: &str) -> Result<ParsedDat> {
                     parse_unchecked(dat)
                 }
-                fn [<try_from_ $lower _buf>]<R: std::io::BufRead>(buf: R) -> Result<Vec<Result<GameEntry>>> {
+                fn [<try_from_ $lower _buf>]<R: std::io::BufRead>(buf: R) -> Result<ParsedDat> {
                     parse_buf(buf)
                 }
                 fn [<try_unchecked_from_ $lower _buf>]<R: std::io::BufRead>(
                     buf: R,
-                ) -> Result<Vec<Result<GameEntry>>> {
+                ) -> Result<ParsedDat> {
                     parse_unchecked_buf(buf)
                 }
+                fn [<try_iter_from_ $lower _buf>]<R: std::io::BufRead>(buf: R) -> Result<DatReader<R>> {
+                    parse_iter(buf)
+                }
+                fn [<try_unchecked_iter_from_ $lower _buf>]<R: std::io::BufRead>(
+                    buf: R,
+                ) -> Result<DatReader<R>> {
+                    parse_unchecked_iter(buf)
+                }
             }
         }
     }
@@ -7,6 +7,7 @@ use shiratsu_naming::naming::NameError;
 #[derive(Debug)]
 pub enum DatError {
     ParseError(String),
+    WriteError(String),
     NameError(NameError),
     HeaderMismatchError(&'static str, Option<String>),
 }
@@ -23,6 +24,7 @@ impl Display for DatError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             DatError::ParseError(val) => write!(f, "{}", val),
+            DatError::WriteError(val) => write!(f, "{}", val),
             DatError::HeaderMismatchError(expected, actual) =>
                 write!(f,
                        "Expected DAT to have header homepage \"{}\" but it actually was \"{}\". Use unchecked variants to ignore header checking.",
@@ -1,25 +1,31 @@
 use quick_xml::de::DeError as XmlError;
 use serde::Deserialize;
-use std::convert::{TryFrom, TryInto};
+use std::convert::TryFrom;
 
 use crate::error::*;
 
-use super::super::xml::*;
+use super::super::reader::*;
 use super::super::*;
 
 #[derive(Debug, Deserialize, PartialEq)]
 struct Rom {
     name: String,
     size: i64,
-    crc: String,
-    md5: String,
-    sha1: String,
+    crc: Option<String>,
+    md5: Option<String>,
+    sha1: Option<String>,
+    sha256: Option<String>,
+    xxh3: Option<String>,
+    status: Option<String>,
 }
 
 #[derive(Debug, Deserialize, PartialEq)]
 struct Game {
     name: String,
+    cloneof: Option<String>,
+    romof: Option<String>,
     rom: Vec<Rom>,
+    serial: Option<String>,
 }
 
 impl TryFrom<Game> for GameEntry {
@@ -30,26 +36,35 @@ impl TryFrom<Game> for GameEntry {
         Ok(GameEntry {
             info: None,
             entry_name: name,
-            serials: vec![],
-            rom_entries: rom.into_iter().map(|r| r.into()).collect(),
+            serials: game
+                .serial
+                .map(|s| {
+                    s.split(",")
+                        .map(|s| Serial::new(String::from(s.trim())))
+                        .collect()
+                })
+                .unwrap_or(vec![]),
+            rom_entries: rom.into_iter().map(RomEntry::try_from).collect::<Result<_>>()?,
             source: "Generic",
+            parent_name: game.cloneof,
+            rom_of: game.romof,
         })
     }
 }
 
-impl From<Rom> for RomEntry {
-    fn from(mut rom: Rom) -> Self {
-        rom.md5.make_ascii_lowercase();
-        rom.crc.make_ascii_lowercase();
-        rom.sha1.make_ascii_lowercase();
-        
-        RomEntry {
-            md5: Some(rom.md5),
-            sha1: Some(rom.sha1),
-            crc: Some(rom.crc),
-            file_name: rom.name,
-            size: rom.size,
-        }
+impl TryFrom<Rom> for RomEntry {
+    type Error = DatError;
+    fn try_from(rom: Rom) -> Result<Self> {
+        RomEntry::from_attributes(
+            rom.name,
+            Some(rom.size),
+            rom.crc,
+            rom.md5,
+            rom.sha1,
+            rom.sha256,
+            rom.xxh3,
+            rom.status.as_deref(),
+        )
     }
 }
 
@@ -61,37 +76,39 @@ wrap_error! {
     }
 }
 
-fn parse_unchecked(f: &str) -> Result<Vec<Result<GameEntry>>> {
-    Ok(parse_dat_unchecked::<Game, GenericParserError>(f)?
-        .game
-        .into_iter()
-        .map(|g| g.try_into())
-        .collect())
+fn parse_unchecked(f: &str) -> Result<ParsedDat> {
+    Ok(parse_unchecked_iter(f.as_bytes())?.into())
+}
+
+fn parse_unchecked_buf<R: std::io::BufRead>(f: R) -> Result<ParsedDat> {
+    Ok(parse_unchecked_iter(f)?.into())
 }
 
-fn parse_unchecked_buf<R: std::io::BufRead>(f: R) -> Result<Vec<Result<GameEntry>>> {
-    Ok(parse_dat_unchecked_buf::<R, Game, GenericParserError>(f)?
-        .game
-        .into_iter()
-        .map(|g| g.try_into())
-        .collect())
+fn parse_unchecked_iter<R: std::io::BufRead>(f: R) -> Result<DatReader<R>> {
+    DatReader::new::<Game, GenericParserError>(f, None)
 }
 
-/// Provides methods that parse XML .dat files
+/// Provides methods that parse Logiqx XML, ClrMamePro, or RomCenter .dat files
 pub trait FromGeneric {
 
-    /// Parses the contents of a generic DAT XML
-    fn try_from_str(dat: &str) -> Result<Vec<Result<GameEntry>>>;
+    /// Parses the contents of a generic DAT
+    fn try_from_str(dat: &str) -> Result<ParsedDat>;
+
+    /// Parses the contents of a generic DAT
+    fn try_from_buf<R: std::io::BufRead>(buf: R) -> Result<ParsedDat>;
 
-    /// Parses the contents of a generic DAT XML
-    fn try_from_buf<R: std::io::BufRead>(buf: R) -> Result<Vec<Result<GameEntry>>>;
+    /// Streams the contents of a generic DAT, yielding `GameEntries` as they are read.
+    fn try_iter_from_buf<R: std::io::BufRead>(buf: R) -> Result<DatReader<R>>;
 }
 
 impl FromGeneric for GameEntry {
-    fn try_from_str(dat: &str) -> Result<Vec<Result<GameEntry>>> {
+    fn try_from_str(dat: &str) -> Result<ParsedDat> {
         parse_unchecked(dat)
     }
-    fn try_from_buf<R: std::io::BufRead>(buf: R) -> Result<Vec<Result<GameEntry>>> {
+    fn try_from_buf<R: std::io::BufRead>(buf: R) -> Result<ParsedDat> {
         parse_unchecked_buf(buf)
     }
+    fn try_iter_from_buf<R: std::io::BufRead>(buf: R) -> Result<DatReader<R>> {
+        parse_unchecked_iter(buf)
+    }
 }
@@ -2,6 +2,10 @@
 pub(crate) mod common;
 
 mod xml;
+mod clrmamepro;
+mod romcenter;
+mod reader;
+mod writer;
 
 pub mod nointro;
 pub mod redump;
@@ -9,20 +13,24 @@ pub mod tosec;
 pub mod dats_site;
 pub mod opengood;
 pub mod generic;
+pub mod selection;
 mod error;
 
 pub use common::*;
 pub use error::*;
+pub use reader::{DatReader, ParsedDat};
+pub use writer::DatWriter;
 
 
 #[cfg(test)]
 mod tests {
 
-    use crate::NameInfo;
+    use crate::{DumpQuality, NameInfo};
 
     use shiratsu_naming::naming::tosec::TOSECName;
     use shiratsu_naming::region::Region;
     use shiratsu_naming::naming::nointro::NoIntroName;
+    use shiratsu_naming::naming::goodtools::GoodToolsName;
     use shiratsu_naming::naming::TokenizedName;
 
     #[test]
@@ -135,5 +143,59 @@ mod tests {
         assert_eq!(&[Region::Unknown], parsed.region());
         assert_eq!(Some("1"), parsed.version());
     }
-}
 
+    #[test]
+    fn nointro_filename_parses_languages() {
+        let parsed: NameInfo = NoIntroName::try_parse("Pokemon Crystal (Europe) (En,Fr,De,Zh-Hant)").unwrap().into();
+        assert_eq!(&["en", "fr", "de", "zh"], parsed.languages());
+        let parsed: NameInfo = NoIntroName::try_parse("Tetris (World)").unwrap().into();
+        assert!(parsed.languages().is_empty());
+    }
+
+    #[test]
+    fn tosec_filename_parses_languages() {
+        let parsed: NameInfo = TOSECName::try_parse("Legend of TOSEC, The (1986)(Devstudio)(US)(en-ja)").unwrap().into();
+        assert_eq!(&["en", "ja"], parsed.languages());
+        let parsed: NameInfo = TOSECName::try_parse("Legend of TOSEC, The (1986)(Devstudio)(M6)").unwrap().into();
+        assert!(parsed.languages().is_empty());
+    }
+
+    #[test]
+    fn tosec_filename_parses_date_and_publishers() {
+        let parsed: NameInfo = TOSECName::try_parse("2600 Digital Clock - Demo 1 (demo)(1997-10-03)(Cracknell, Chris 'Crackers' - Doe, John)(NTSC)(PD)").unwrap().into();
+        assert_eq!(Some(1997), parsed.release_year());
+        assert_eq!(Some("1997-10-03"), parsed.release_date());
+        assert_eq!(&["Cracknell, Chris 'Crackers'", "Doe, John"], parsed.publishers());
+
+        let parsed: NameInfo = TOSECName::try_parse("Bombsawa (Jumpman Selected levels)(19XX)(-)(PD)").unwrap().into();
+        assert_eq!(None, parsed.release_year());
+        assert_eq!(Some("19xx"), parsed.release_date());
+        assert!(parsed.publishers().is_empty());
+    }
+
+    #[test]
+    fn goodtools_filename_parses_year() {
+        let parsed: NameInfo = GoodToolsName::try_parse("Tetris (1989) (Nintendo) [!]").unwrap().into();
+        assert_eq!(Some(1989), parsed.release_year());
+        assert_eq!(Some("1989"), parsed.release_date());
+    }
+
+    #[test]
+    fn tosec_filename_parses_dump_quality() {
+        let parsed: NameInfo = TOSECName::try_parse("Legend of TOSEC, The (1986)(Devstudio)[cr3 +test][h][a2][h Fix]").unwrap().into();
+        assert_eq!(&[DumpQuality::Cracked, DumpQuality::Hack, DumpQuality::Alternate], parsed.dump_quality());
+        assert!(!parsed.is_unlicensed());
+
+        let parsed: NameInfo = TOSECName::try_parse("Legend of TOSEC, The (1986)(Devstudio)[p][!]").unwrap().into();
+        assert_eq!(&[DumpQuality::Pirate, DumpQuality::Verified], parsed.dump_quality());
+        assert!(parsed.is_unlicensed());
+    }
+
+    #[test]
+    fn goodtools_filename_parses_dump_quality() {
+        let parsed: NameInfo = GoodToolsName::try_parse("Tetris (1989) (Nintendo) [b1][T+Fre]").unwrap().into();
+        assert_eq!(&[DumpQuality::BadDump, DumpQuality::Translation], parsed.dump_quality());
+        let parsed: NameInfo = GoodToolsName::try_parse("Tetris (Adventure Hack) [!]").unwrap().into();
+        assert_eq!(&[DumpQuality::Hack, DumpQuality::Verified], parsed.dump_quality());
+    }
+}
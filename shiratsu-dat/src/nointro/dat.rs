@@ -1,7 +1,7 @@
 use crate::error::*;
 
 use serde::Deserialize;
-use std::convert::{TryFrom, TryInto};
+use std::convert::TryFrom;
 
 use quick_xml::de::DeError as XmlError;
 
@@ -12,15 +12,20 @@ use shiratsu_naming::naming::TokenizedName;
 struct Rom {
     name: String,
     size: Option<i64>,
-    crc: String,
-    md5: String,
-    sha1: String,
+    crc: Option<String>,
+    md5: Option<String>,
+    sha1: Option<String>,
+    sha256: Option<String>,
+    xxh3: Option<String>,
+    status: Option<String>,
     serial: Option<String>,
 }
 
 #[derive(Debug, Deserialize, PartialEq)]
 struct Game {
     name: String,
+    cloneof: Option<String>,
+    romof: Option<String>,
     rom: Vec<Rom>,
 }
 
@@ -41,25 +46,27 @@ impl TryFrom<Game> for GameEntry {
                         .collect::<Vec<_>>()
                 })
                 .collect(),
-            rom_entries: rom.into_iter().map(|r| r.into()).collect(),
+            rom_entries: rom.into_iter().map(RomEntry::try_from).collect::<Result<_>>()?,
             source: "No-Intro",
+            parent_name: game.cloneof,
+            rom_of: game.romof,
         })
     }
 }
 
-impl From<Rom> for RomEntry {
-    fn from(mut rom: Rom) -> Self {
-        rom.md5.make_ascii_lowercase();
-        rom.crc.make_ascii_lowercase();
-        rom.sha1.make_ascii_lowercase();
-
-        RomEntry {
-            md5: Some(rom.md5),
-            sha1: Some(rom.sha1),
-            crc: Some(rom.crc),
-            file_name: rom.name,
-            size: rom.size.unwrap_or(0),
-        }
+impl TryFrom<Rom> for RomEntry {
+    type Error = DatError;
+    fn try_from(rom: Rom) -> Result<Self> {
+        RomEntry::from_attributes(
+            rom.name,
+            rom.size,
+            rom.crc,
+            rom.md5,
+            rom.sha1,
+            rom.sha256,
+            rom.xxh3,
+            rom.status.as_deref(),
+        )
     }
 }
 
@@ -0,0 +1,152 @@
+use crate::clrmamepro::ClrMameProReader;
+use crate::error::{DatError, Result as DatResult};
+use crate::romcenter::RomCenterReader;
+use crate::xml::XmlReader;
+use crate::{DatHeader, GameEntry};
+use quick_xml::de::DeError;
+use serde::de::DeserializeOwned;
+use std::borrow::Cow;
+use std::convert::TryInto;
+use std::io::BufRead;
+
+/// Streams `GameEntries` out of a DAT, one game at a time.
+///
+/// Logiqx XML, ClrMamePro, and RomCenter DATs are read, detected by the first character of the DAT.
+/// The header is read and checked when the reader is created, before any
+/// entries are yielded. Errors in a single game are yielded in place of that entry,
+/// while a malformed DAT ends iteration after yielding the error.
+pub struct DatReader<R: BufRead> {
+    inner: Format<R>,
+    header: Option<DatHeader>,
+}
+
+enum Format<R: BufRead> {
+    Xml(XmlReader<R>),
+    ClrMamePro(ClrMameProReader<R>),
+    RomCenter(RomCenterReader<R>),
+}
+
+impl<R: BufRead> DatReader<R> {
+    pub(crate) fn new<
+        G: DeserializeOwned + TryInto<GameEntry, Error = DatError>,
+        E: Into<DatError> + From<DeError>,
+    >(
+        mut f: R,
+        expect_homepage: Option<&'static str>,
+    ) -> DatResult<DatReader<R>> {
+        let (inner, header) = match peek_first_char(&mut f)? {
+            Some(b'<') | None => {
+                let mut reader = XmlReader::new::<G, E>(f);
+                let header = reader.read_header()?;
+                (Format::Xml(reader), header)
+            }
+            Some(b'[') => {
+                let mut reader = RomCenterReader::new::<G>(f);
+                let header = reader.read_header()?;
+                (Format::RomCenter(reader), header)
+            }
+            Some(_) => {
+                let mut reader = ClrMameProReader::new::<G>(f);
+                let header = reader.read_header()?;
+                (Format::ClrMamePro(reader), header)
+            }
+        };
+
+        if let Some(expected) = expect_homepage {
+            let homepage = header.as_ref().and_then(|h| h.homepage());
+            if homepage != Some(expected) {
+                return Err(DatError::HeaderMismatchError(expected, homepage.map(String::from)));
+            }
+        }
+        Ok(DatReader { inner, header })
+    }
+
+    /// The header of the DAT, if it has one.
+    pub fn header(&self) -> Option<&DatHeader> {
+        self.header.as_ref()
+    }
+}
+
+impl<R: BufRead> Iterator for DatReader<R> {
+    type Item = DatResult<GameEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            Format::Xml(reader) => reader.next(),
+            Format::ClrMamePro(reader) => reader.next(),
+            Format::RomCenter(reader) => reader.next(),
+        }
+    }
+}
+
+/// The contents of a DAT, read in full.
+#[derive(Debug)]
+pub struct ParsedDat {
+    header: Option<DatHeader>,
+    entries: Vec<DatResult<GameEntry>>,
+}
+
+impl ParsedDat {
+    /// The header of the DAT, if it has one.
+    pub fn header(&self) -> Option<&DatHeader> {
+        self.header.as_ref()
+    }
+    /// The game entries of the DAT, or the errors encountered while parsing them.
+    pub fn entries(&self) -> &[DatResult<GameEntry>] {
+        &self.entries
+    }
+    /// Consumes the `ParsedDat`, returning its header and entries.
+    pub fn into_parts(self) -> (Option<DatHeader>, Vec<DatResult<GameEntry>>) {
+        (self.header, self.entries)
+    }
+}
+
+impl<R: BufRead> From<DatReader<R>> for ParsedDat {
+    fn from(mut reader: DatReader<R>) -> Self {
+        let header = reader.header.take();
+        ParsedDat {
+            header,
+            entries: reader.collect(),
+        }
+    }
+}
+
+impl IntoIterator for ParsedDat {
+    type Item = DatResult<GameEntry>;
+    type IntoIter = std::vec::IntoIter<DatResult<GameEntry>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+/// Skips any leading whitespace or byte order mark, and returns the first character of the DAT
+/// without consuming it.
+fn peek_first_char<R: BufRead>(f: &mut R) -> DatResult<Option<u8>> {
+    loop {
+        let buf = f
+            .fill_buf()
+            .map_err(|err| DatError::ParseError(format!("Error reading DAT: {}", err)))?;
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        let skip = buf
+            .iter()
+            .take_while(|&&b| b.is_ascii_whitespace() || b == 0xEF || b == 0xBB || b == 0xBF)
+            .count();
+        if skip < buf.len() {
+            let first = buf[skip];
+            f.consume(skip);
+            return Ok(Some(first));
+        }
+        f.consume(skip);
+    }
+}
+
+/// Decodes a line of a text DAT as UTF-8, falling back to Latin-1 for older DATs.
+pub(crate) fn decode_line(line: &[u8]) -> Cow<'_, str> {
+    match std::str::from_utf8(line) {
+        Ok(line) => Cow::Borrowed(line),
+        Err(_) => Cow::Owned(line.iter().map(|&b| b as char).collect()),
+    }
+}
@@ -2,7 +2,7 @@ use crate::error::*;
 
 use quick_xml::de::DeError as XmlError;
 use serde::Deserialize;
-use std::convert::{TryFrom, TryInto};
+use std::convert::TryFrom;
 
 use shiratsu_naming::naming::nointro::NoIntroName;
 use shiratsu_naming::naming::TokenizedName;
@@ -11,14 +11,19 @@ use shiratsu_naming::naming::TokenizedName;
 struct Rom {
     name: String,
     size: i64,
-    crc: String,
-    md5: String,
-    sha1: String,
+    crc: Option<String>,
+    md5: Option<String>,
+    sha1: Option<String>,
+    sha256: Option<String>,
+    xxh3: Option<String>,
+    status: Option<String>,
 }
 
 #[derive(Debug, Deserialize, PartialEq)]
 struct Game {
     name: String,
+    cloneof: Option<String>,
+    romof: Option<String>,
     rom: Vec<Rom>,
     serial: Option<String>,
 }
@@ -39,25 +44,27 @@ impl TryFrom<Game> for GameEntry {
                         .collect()
                 })
                 .unwrap_or(vec![]),
-            rom_entries: rom.into_iter().map(|r| r.into()).collect(),
+            rom_entries: rom.into_iter().map(RomEntry::try_from).collect::<Result<_>>()?,
             source: "Redump",
+            parent_name: game.cloneof,
+            rom_of: game.romof,
         })
     }
 }
 
-impl From<Rom> for RomEntry {
-    fn from(mut rom: Rom) -> Self {
-        rom.md5.make_ascii_lowercase();
-        rom.crc.make_ascii_lowercase();
-        rom.sha1.make_ascii_lowercase();
-
-        RomEntry {
-            md5: Some(rom.md5),
-            sha1: Some(rom.sha1),
-            crc: Some(rom.crc),
-            file_name: rom.name,
-            size: rom.size,
-        }
+impl TryFrom<Rom> for RomEntry {
+    type Error = DatError;
+    fn try_from(rom: Rom) -> Result<Self> {
+        RomEntry::from_attributes(
+            rom.name,
+            Some(rom.size),
+            rom.crc,
+            rom.md5,
+            rom.sha1,
+            rom.sha256,
+            rom.xxh3,
+            rom.status.as_deref(),
+        )
     }
 }
 
@@ -0,0 +1,332 @@
+use crate::clrmamepro::{ClrMameProError, Field, Node, Result};
+use crate::error::{DatError, Result as DatResult};
+use crate::reader::decode_line;
+use crate::{DatHeader, GameEntry};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::convert::TryInto;
+use std::io::BufRead;
+
+/// A single row of the `[GAMES]` section, describing one ROM of a game.
+struct Row {
+    parent_name: String,
+    game_name: String,
+    game_description: String,
+    rom_of: String,
+    rom: Node,
+}
+
+impl Row {
+    /// Parses a row of the form `¬parent name¬parent description¬game name¬game description¬rom name¬rom crc¬rom size¬romof name¬merge name¬`.
+    fn parse(line: &str) -> Result<Row> {
+        let fields: Vec<&str> = line.strip_prefix('¬').unwrap_or(line).split('¬').collect();
+        if fields.len() < 7 {
+            return Err(ClrMameProError(format!("Malformed RomCenter row {}", line)));
+        }
+
+        let mut rom = vec![
+            ("name".to_string(), Node::Value(fields[4].to_string())),
+            ("size".to_string(), Node::Value(fields[6].to_string())),
+        ];
+        if !fields[5].is_empty() {
+            rom.push(("crc".to_string(), Node::Value(fields[5].to_string())));
+        }
+
+        Ok(Row {
+            parent_name: fields[0].to_string(),
+            game_name: fields[2].to_string(),
+            game_description: fields[3].to_string(),
+            rom_of: fields.get(7).copied().unwrap_or_default().to_string(),
+            rom: Node::List(rom),
+        })
+    }
+}
+
+/// Reads games out of a RomCenter DAT, one at a time.
+///
+/// Each row of the `[GAMES]` section describes a single ROM, and consecutive rows
+/// with the same game name are collected into one game.
+pub(crate) struct RomCenterReader<R: BufRead> {
+    reader: R,
+    line: Vec<u8>,
+    in_games: bool,
+    pending: Option<Row>,
+    parse_game: fn(Node) -> DatResult<GameEntry>,
+    done: bool,
+}
+
+impl<R: BufRead> RomCenterReader<R> {
+    pub(crate) fn new<G: DeserializeOwned + TryInto<GameEntry, Error = DatError>>(
+        f: R,
+    ) -> RomCenterReader<R> {
+        RomCenterReader {
+            reader: f,
+            line: Vec::new(),
+            in_games: false,
+            pending: None,
+            parse_game: parse_game::<G>,
+            done: false,
+        }
+    }
+
+    /// Reads up to the start of the `[GAMES]` section.
+    ///
+    /// The header is made up of the `[CREDITS]` section, the name of the emulator in
+    /// the `[EMULATOR]` section, and the RomCenter options in the `[DAT]` section.
+    pub(crate) fn read_header(&mut self) -> DatResult<Option<DatHeader>> {
+        let mut header = Vec::new();
+        let mut options = Vec::new();
+        let mut merge = None;
+        let mut split = None;
+        let mut has_header = false;
+        let mut section = String::new();
+        while let Some(line) = self.read_line()? {
+            if let Some(name) = section_name(&line) {
+                section = name.to_ascii_uppercase();
+                if section == "GAMES" {
+                    self.in_games = true;
+                    break;
+                }
+                has_header = true;
+                continue;
+            }
+
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim().to_ascii_lowercase(), Node::Value(value.trim().to_string())),
+                None => continue,
+            };
+            match (section.as_str(), key.as_str()) {
+                ("CREDITS", _) => header.push((key, value)),
+                ("EMULATOR", "refname") => header.push(("name".to_string(), value)),
+                ("EMULATOR", "version") => header.push(("description".to_string(), value)),
+                ("DAT", "plugin") => options.push((key, value)),
+                ("DAT", "merge") => merge = Some(value == Node::Value("1".to_string())),
+                ("DAT", "split") => split = Some(value == Node::Value("1".to_string())),
+                _ => {}
+            }
+        }
+
+        // A merged set is also split, so merge takes precedence over split.
+        let rom_mode = match (merge, split) {
+            (Some(true), _) => Some("merged"),
+            (_, Some(true)) => Some("split"),
+            (None, None) => None,
+            _ => Some("unmerged"),
+        };
+        if let Some(rom_mode) = rom_mode {
+            options.push(("rommode".to_string(), Node::Value(rom_mode.to_string())));
+        }
+
+        if !has_header {
+            return Ok(None);
+        }
+        if !options.is_empty() {
+            header.push(("romcenter".to_string(), Node::List(options)));
+        }
+        Ok(Some(DatHeader::deserialize(Field(vec![Node::List(header)])).map_err(map_err)?))
+    }
+
+    /// Reads the next non-empty line, without its line ending.
+    fn read_line(&mut self) -> Result<Option<String>> {
+        loop {
+            self.line.clear();
+            let read = self
+                .reader
+                .read_until(b'\n', &mut self.line)
+                .map_err(|err| ClrMameProError(err.to_string()))?;
+            if read == 0 {
+                return Ok(None);
+            }
+            let line = decode_line(&self.line);
+            let line = line.trim();
+            if !line.is_empty() {
+                return Ok(Some(line.to_string()));
+            }
+        }
+    }
+
+    fn read_row(&mut self) -> Result<Option<Row>> {
+        while let Some(line) = self.read_line()? {
+            if let Some(name) = section_name(&line) {
+                self.in_games = name.eq_ignore_ascii_case("GAMES");
+            } else if self.in_games && line.starts_with('¬') {
+                return Ok(Some(Row::parse(&line)?));
+            }
+        }
+        Ok(None)
+    }
+
+    fn read_game(&mut self) -> Result<Option<Node>> {
+        let first = match self.pending.take() {
+            Some(row) => row,
+            None => match self.read_row()? {
+                Some(row) => row,
+                None => return Ok(None),
+            },
+        };
+
+        let mut game = vec![
+            ("name".to_string(), Node::Value(first.game_name.clone())),
+            ("description".to_string(), Node::Value(first.game_description)),
+        ];
+        if !first.parent_name.is_empty() && first.parent_name != first.game_name {
+            game.push(("cloneof".to_string(), Node::Value(first.parent_name)));
+        }
+        if !first.rom_of.is_empty() {
+            game.push(("romof".to_string(), Node::Value(first.rom_of)));
+        }
+        game.push(("rom".to_string(), first.rom));
+        while let Some(row) = self.read_row()? {
+            if row.game_name != first.game_name {
+                self.pending = Some(row);
+                break;
+            }
+            game.push(("rom".to_string(), row.rom));
+        }
+        Ok(Some(Node::List(game)))
+    }
+}
+
+impl<R: BufRead> Iterator for RomCenterReader<R> {
+    type Item = DatResult<GameEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.read_game() {
+            Ok(Some(game)) => Some((self.parse_game)(game)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(map_err(err)))
+            }
+        }
+    }
+}
+
+/// Gets the name of the section if the line is a section header like `[GAMES]`.
+fn section_name(line: &str) -> Option<&str> {
+    line.strip_prefix('[').and_then(|line| line.strip_suffix(']'))
+}
+
+fn parse_game<G: DeserializeOwned + TryInto<GameEntry, Error = DatError>>(game: Node) -> DatResult<GameEntry> {
+    let game = G::deserialize(Field(vec![game])).map_err(map_err)?;
+    game.try_into()
+}
+
+fn map_err(err: ClrMameProError) -> DatError {
+    DatError::ParseError(format!("Error parsing RomCenter DAT: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generic::FromGeneric;
+    use crate::nointro::FromNoIntro;
+    use crate::tosec::FromTOSEC;
+    use crate::{DatError, GameEntry};
+
+    const TOSEC_DAT: &[u8] = b"[CREDITS]\r
+author=TOSEC\r
+version=2020-01-01\r
+homepage=TOSEC\r
+[DAT]\r
+version=2.50\r
+plugin=arcade.dll\r
+split=0\r
+merge=0\r
+[EMULATOR]\r
+refname=Atari 2600 - Games\r
+version=Atari 2600 - Games (TOSEC-v2020-01-01)\r
+[GAMES]\r
+\xACAdventure (1980)(Atari)\xACAdventure (1980)(Atari)\xACAdventure (1980)(Atari)\xACAdventure (1980)(Atari)\xACAdventure (1980)(Atari).a26\xAC157356f8\xAC4096\xAC\xAC\xAC\r
+\xACCombat (1977)(Atari)\xACCombat (1977)(Atari)\xACCombat (1977)(Atari)\xACCombat (1977)(Atari)\xACCombat (1977)(Atari)(Side A).a26\xAC0ef64cdb\xAC2048\xAC\xAC\xAC\r
+\xACCombat (1977)(Atari)\xACCombat (1977)(Atari)\xACCombat (1977)(Atari)\xACCombat (1977)(Atari)\xACCombat (1977)(Atari)(Side B).a26\xAC0ef64cdc\xAC2048\xAC\xAC\xAC\r
+";
+
+    #[test]
+    fn parses_romcenter_dat() {
+        let entries: Vec<GameEntry> = GameEntry::try_from_tosec_buf(TOSEC_DAT)
+            .unwrap()
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(2, entries.len());
+        assert_eq!("Adventure (1980)(Atari)", entries[0].entry_name());
+        assert_eq!("Adventure", entries[0].info().unwrap().entry_title());
+        assert_eq!("157356f8", entries[0].rom_entries()[0].hash_crc().unwrap().to_string());
+        assert_eq!(None, entries[0].rom_entries()[0].hash_md5());
+        assert_eq!(None, entries[0].rom_entries()[0].hash_sha1());
+        assert_eq!(4096, entries[0].rom_entries()[0].size());
+        assert_eq!(2, entries[1].rom_entries().len());
+        assert_eq!("Combat (1977)(Atari)(Side B).a26", entries[1].rom_entries()[1].file_name());
+    }
+
+    #[test]
+    fn parses_romcenter_parents() {
+        let dat = "[CREDITS]
+homepage=TOSEC
+[GAMES]
+¬Combat (1977)(Atari)¬Combat (1977)(Atari)¬Combat (1977)(Atari)¬Combat (1977)(Atari)¬Combat (1977)(Atari).a26¬0ef64cdb¬2048¬¬¬
+¬Combat (1977)(Atari)¬Combat (1977)(Atari)¬Combat (1977)(Atari)(a)¬Combat (1977)(Atari)(a)¬Combat (1977)(Atari)(a).a26¬0ef64cdc¬2048¬Combat (1977)(Atari)¬¬
+";
+        let entries: Vec<GameEntry> = GameEntry::try_from_tosec_str(dat)
+            .unwrap()
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(None, entries[0].parent_name());
+        assert_eq!(Some("Combat (1977)(Atari)"), entries[1].parent_name());
+        assert_eq!(Some("Combat (1977)(Atari)"), entries[1].rom_of());
+    }
+
+    #[test]
+    fn checks_romcenter_header() {
+        match GameEntry::try_from_nointro_buf(TOSEC_DAT) {
+            Err(DatError::HeaderMismatchError("No-Intro", Some(homepage))) => assert_eq!("TOSEC", homepage),
+            _ => panic!("expected header mismatch"),
+        }
+        assert_eq!(2, GameEntry::try_from_buf(TOSEC_DAT).unwrap().entries().len());
+    }
+
+    #[test]
+    fn parses_romcenter_header() {
+        let dat = GameEntry::try_from_tosec_buf(TOSEC_DAT).unwrap();
+        let header = dat.header().unwrap();
+        assert_eq!(Some("Atari 2600 - Games"), header.name());
+        assert_eq!(Some("2020-01-01"), header.version());
+        assert_eq!(Some("TOSEC"), header.author());
+        assert_eq!(Some("arcade.dll"), header.romcenter().unwrap().plugin());
+        assert_eq!(Some("unmerged"), header.romcenter().unwrap().rom_mode());
+    }
+
+    #[test]
+    fn parses_romcenter_rom_mode() {
+        let dat = |merge: &str, split: &str| {
+            format!("[CREDITS]\nhomepage=TOSEC\n[DAT]\nsplit={}\nmerge={}\n[GAMES]\n", split, merge)
+        };
+        let rom_mode = |dat: String| {
+            GameEntry::try_from_tosec_str(&dat)
+                .unwrap()
+                .header()
+                .unwrap()
+                .romcenter()
+                .unwrap()
+                .rom_mode()
+                .map(str::to_string)
+        };
+        assert_eq!(Some("merged".to_string()), rom_mode(dat("1", "1")));
+        assert_eq!(Some("split".to_string()), rom_mode(dat("0", "1")));
+        assert_eq!(Some("unmerged".to_string()), rom_mode(dat("0", "0")));
+
+        let malformed = "[CREDITS]\nhomepage=TOSEC\n[GAMES]\n¬Combat¬\n";
+        let entries: Vec<_> = GameEntry::try_iter_from_tosec_buf(malformed.as_bytes()).unwrap().collect();
+        match &entries[0] {
+            Err(DatError::ParseError(msg)) => assert!(msg.starts_with("Error parsing RomCenter DAT")),
+            _ => panic!("expected parse error"),
+        }
+    }
+}
@@ -0,0 +1,367 @@
+//! 1G1R (one game, one ROM) selection of a single preferred game entry out of
+//! each group of related game entries.
+
+use crate::{DevelopmentStatus, GameEntry};
+use shiratsu_naming::region::Region;
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// How game entries are grouped together before a single entry is selected out of each group.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Grouping {
+    /// Clones are grouped with their parent, as declared by the `cloneof` attribute of the DAT.
+    ParentClone,
+    /// Game entries are grouped by their entry title and part number, as parsed from their name.
+    Title,
+}
+
+impl Grouping {
+    /// Groups by parent and clone if any of the entries declares a parent, and by title otherwise.
+    pub fn infer<'a, I: IntoIterator<Item = &'a GameEntry>>(entries: I) -> Grouping {
+        if entries.into_iter().any(|e| e.parent_name().is_some()) {
+            Grouping::ParentClone
+        } else {
+            Grouping::Title
+        }
+    }
+
+    fn key<'a>(&self, entry: &'a GameEntry) -> (&'a str, Option<i32>) {
+        match self {
+            Grouping::ParentClone => (entry.parent_name().unwrap_or(entry.entry_name()), None),
+            Grouping::Title => entry
+                .info()
+                .map(|i| (i.entry_title(), i.part_number()))
+                .unwrap_or((entry.entry_name(), None)),
+        }
+    }
+}
+
+/// Groups related game entries together, in the order each group was first encountered.
+pub fn group_entries<'a, I: IntoIterator<Item = &'a GameEntry>>(
+    entries: I,
+    grouping: Grouping,
+) -> Vec<Vec<&'a GameEntry>> {
+    let mut groups: Vec<Vec<&GameEntry>> = Vec::new();
+    let mut indices = HashMap::new();
+    for entry in entries {
+        let index = *indices.entry(grouping.key(entry)).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[index].push(entry);
+    }
+    groups
+}
+
+/// The preferences used to select a single game entry out of a group of related game entries.
+///
+/// Entries are first filtered by their flags and development status, then compared by
+/// region, language, development status, and version, in that order.
+#[derive(Debug, Clone)]
+pub struct Priority {
+    regions: Vec<Vec<Region>>,
+    languages: Vec<String>,
+    statuses: Vec<DevelopmentStatus>,
+    prefer_latest_version: bool,
+    allow_demos: bool,
+    allow_unlicensed: bool,
+    allow_system: bool,
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Priority {
+            regions: vec![vec![Region::UnitedStates], vec![Region::Europe], vec![Region::Japan]],
+            languages: Vec::new(),
+            statuses: vec![DevelopmentStatus::Release],
+            prefer_latest_version: true,
+            allow_demos: false,
+            allow_unlicensed: true,
+            allow_system: true,
+        }
+    }
+}
+
+impl Priority {
+    /// Sets the regions in order of preference.
+    ///
+    /// Entries released in none of the given regions are least preferred.
+    pub fn with_regions(mut self, regions: Vec<Region>) -> Self {
+        self.regions = regions.into_iter().map(|r| vec![r]).collect();
+        self
+    }
+
+    /// Sets sets of regions in order of preference, where an entry only has a set of regions
+    /// if it was released in every region of the set, such as the US, Europe and Japan for `World`.
+    ///
+    /// Entries released in none of the given sets of regions are least preferred.
+    pub fn with_region_sets(mut self, regions: Vec<Vec<Region>>) -> Self {
+        self.regions = regions;
+        self
+    }
+
+    /// Sets the ISO 639-1 language codes in order of preference.
+    ///
+    /// Entries with none of the given languages are least preferred.
+    pub fn with_languages(mut self, languages: Vec<String>) -> Self {
+        self.languages = languages;
+        self
+    }
+
+    /// Sets the development statuses that may be selected, in order of preference.
+    pub fn with_statuses(mut self, statuses: Vec<DevelopmentStatus>) -> Self {
+        self.statuses = statuses;
+        self
+    }
+
+    /// Sets whether later versions or revisions of an entry are preferred over earlier ones.
+    pub fn prefer_latest_version(mut self, prefer: bool) -> Self {
+        self.prefer_latest_version = prefer;
+        self
+    }
+
+    /// Sets whether demos and samples may be selected.
+    pub fn allow_demos(mut self, allow: bool) -> Self {
+        self.allow_demos = allow;
+        self
+    }
+
+    /// Sets whether unlicensed releases may be selected.
+    pub fn allow_unlicensed(mut self, allow: bool) -> Self {
+        self.allow_unlicensed = allow;
+        self
+    }
+
+    /// Sets whether system updates and BIOS files may be selected.
+    pub fn allow_system(mut self, allow: bool) -> Self {
+        self.allow_system = allow;
+        self
+    }
+
+    /// Whether or not the entry may be selected at all.
+    ///
+    /// Entries without any information parsed from their name are always allowed.
+    pub fn is_allowed(&self, entry: &GameEntry) -> bool {
+        match entry.info() {
+            None => true,
+            Some(info) => {
+                (self.allow_demos || !info.is_demo())
+                    && (self.allow_unlicensed || !info.is_unlicensed())
+                    && (self.allow_system || !info.is_system())
+                    && self.statuses.contains(&info.development_status())
+            }
+        }
+    }
+
+    /// Compares two entries by preference, where `Ordering::Less` means `a` is preferred over `b`.
+    pub fn compare(&self, a: &GameEntry, b: &GameEntry) -> Ordering {
+        self.region_rank(a)
+            .cmp(&self.region_rank(b))
+            .then_with(|| self.language_rank(a).cmp(&self.language_rank(b)))
+            .then_with(|| self.status_rank(a).cmp(&self.status_rank(b)))
+            .then_with(|| {
+                if self.prefer_latest_version {
+                    compare_versions(version(b), version(a))
+                } else {
+                    compare_versions(version(a), version(b))
+                }
+            })
+            // Prefer the parent over its clones, then fall back to the name to be deterministic.
+            .then_with(|| a.parent_name().is_some().cmp(&b.parent_name().is_some()))
+            .then_with(|| a.entry_name().cmp(b.entry_name()))
+    }
+
+    /// Selects the most preferred entry out of a group of related entries.
+    ///
+    /// Returns `None` if none of the entries are allowed.
+    pub fn select<'a>(&self, group: &[&'a GameEntry]) -> Option<&'a GameEntry> {
+        group
+            .iter()
+            .copied()
+            .filter(|e| self.is_allowed(e))
+            .min_by(|a, b| self.compare(a, b))
+    }
+
+    fn region_rank(&self, entry: &GameEntry) -> usize {
+        entry
+            .info()
+            .and_then(|i| {
+                self.regions
+                    .iter()
+                    .position(|set| !set.is_empty() && set.iter().all(|r| i.region().contains(r)))
+            })
+            .unwrap_or(self.regions.len())
+    }
+
+    fn language_rank(&self, entry: &GameEntry) -> usize {
+        entry
+            .info()
+            .and_then(|i| {
+                self.languages
+                    .iter()
+                    .position(|l| i.languages().iter().any(|e| e.eq_ignore_ascii_case(l)))
+            })
+            .unwrap_or(self.languages.len())
+    }
+
+    fn status_rank(&self, entry: &GameEntry) -> usize {
+        entry
+            .info()
+            .and_then(|i| self.statuses.iter().position(|s| *s == i.development_status()))
+            .unwrap_or(self.statuses.len())
+    }
+}
+
+/// Selects a single preferred entry out of each group of related entries.
+pub fn one_game_one_rom<'a, I: IntoIterator<Item = &'a GameEntry>>(
+    entries: I,
+    grouping: Grouping,
+    priority: &Priority,
+) -> Vec<&'a GameEntry> {
+    group_entries(entries, grouping)
+        .iter()
+        .filter_map(|group| priority.select(group))
+        .collect()
+}
+
+fn version(entry: &GameEntry) -> Option<&str> {
+    entry.info().and_then(|i| i.version())
+}
+
+/// Compares two versions component by component, numerically where possible.
+///
+/// An entry without a version is the original release, and is older than any revision.
+fn compare_versions(a: Option<&str>, b: Option<&str>) -> Ordering {
+    match (a, b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        (Some(a), Some(b)) => {
+            let mut a = a.split('.');
+            let mut b = b.split('.');
+            loop {
+                match (a.next(), b.next()) {
+                    (None, None) => return Ordering::Equal,
+                    (None, Some(_)) => return Ordering::Less,
+                    (Some(_), None) => return Ordering::Greater,
+                    (Some(a), Some(b)) => {
+                        let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+                            (Ok(a), Ok(b)) => a.cmp(&b),
+                            _ => a.cmp(b),
+                        };
+                        if ordering != Ordering::Equal {
+                            return ordering;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nointro::FromNoIntro;
+
+    fn game(name: &str, attrs: &str) -> String {
+        format!(
+            r#"<game name="{0}"{1}><description>{0}</description><rom name="{0}.gb" size="32768" crc="46DF91AD"/></game>"#,
+            name, attrs
+        )
+    }
+
+    fn parse(games: &[String]) -> Vec<GameEntry> {
+        let dat = format!(
+            "<datafile><header><homepage>No-Intro</homepage></header>{}</datafile>",
+            games.concat()
+        );
+        GameEntry::try_from_nointro_str(&dat)
+            .unwrap()
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    fn names<'a>(entries: &[&'a GameEntry]) -> Vec<&'a str> {
+        entries.iter().map(|e| e.entry_name()).collect()
+    }
+
+    #[test]
+    fn selects_by_region_priority() {
+        let entries = parse(&[
+            game("Tetris (Japan)", ""),
+            game("Tetris (Europe)", ""),
+            game("Tetris (USA)", ""),
+            game("Dr. Mario (Japan)", ""),
+        ]);
+        let priority = Priority::default().with_regions(vec![Region::Europe, Region::UnitedStates]);
+        let selected = one_game_one_rom(&entries, Grouping::Title, &priority);
+        assert_eq!(vec!["Tetris (Europe)", "Dr. Mario (Japan)"], names(&selected));
+    }
+
+    #[test]
+    fn selects_by_region_set_and_language() {
+        let entries = parse(&[
+            game("Tetris (Japan)", ""),
+            game("Tetris (Europe) (Fr,De)", ""),
+            game("Tetris (Europe) (En,Fr)", ""),
+            game("Dr. Mario (Japan)", ""),
+            game("Dr. Mario (World)", ""),
+        ]);
+        let world = vec![Region::UnitedStates, Region::Japan, Region::Europe];
+        let priority = Priority::default()
+            .with_region_sets(vec![world, vec![Region::Europe], vec![Region::Japan]])
+            .with_languages(vec!["en".to_string()]);
+        let selected = one_game_one_rom(&entries, Grouping::Title, &priority);
+        assert_eq!(vec!["Tetris (Europe) (En,Fr)", "Dr. Mario (World)"], names(&selected));
+    }
+
+    #[test]
+    fn prefers_latest_revision() {
+        let entries = parse(&[
+            game("Tetris (World)", ""),
+            game("Tetris (World) (Rev 2)", ""),
+            game("Tetris (World) (Rev 10)", ""),
+        ]);
+        let selected = one_game_one_rom(&entries, Grouping::Title, &Priority::default());
+        assert_eq!(vec!["Tetris (World) (Rev 10)"], names(&selected));
+
+        let priority = Priority::default().prefer_latest_version(false);
+        let selected = one_game_one_rom(&entries, Grouping::Title, &priority);
+        assert_eq!(vec!["Tetris (World)"], names(&selected));
+    }
+
+    #[test]
+    fn filters_flags_and_status() {
+        let entries = parse(&[
+            game("Tetris (USA) (Beta)", ""),
+            game("Tetris (USA) (Demo)", ""),
+            game("Tetris (Japan)", ""),
+            game("Pokemon (USA) (Proto)", ""),
+        ]);
+        let selected = one_game_one_rom(&entries, Grouping::Title, &Priority::default());
+        assert_eq!(vec!["Tetris (Japan)"], names(&selected));
+
+        let priority = Priority::default()
+            .with_statuses(vec![DevelopmentStatus::Release, DevelopmentStatus::Prerelease]);
+        let selected = one_game_one_rom(&entries, Grouping::Title, &priority);
+        assert_eq!(vec!["Tetris (USA) (Beta)"], names(&selected));
+    }
+
+    #[test]
+    fn groups_by_parent_clone() {
+        let entries = parse(&[
+            game("Pocket Monsters Aka (Japan)", ""),
+            game("Pokemon - Red Version (USA, Europe)", r#" cloneof="Pocket Monsters Aka (Japan)""#),
+            game("Pokemon - Blue Version (USA, Europe)", ""),
+        ]);
+        assert_eq!(Grouping::ParentClone, Grouping::infer(&entries));
+        let selected = one_game_one_rom(&entries, Grouping::ParentClone, &Priority::default());
+        assert_eq!(
+            vec!["Pokemon - Red Version (USA, Europe)", "Pokemon - Blue Version (USA, Europe)"],
+            names(&selected)
+        );
+    }
+}
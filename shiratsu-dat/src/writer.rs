@@ -0,0 +1,349 @@
+use crate::error::{DatError, Result};
+use crate::{DatHeader, GameEntry, RomEntry, RomStatus};
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::Writer;
+use std::io::Write;
+
+const DOCTYPE: &str = r#" datafile PUBLIC "-//Logiqx//DTD ROM Management Datafile//EN" "http://www.logiqx.com/Dats/datafile.dtd""#;
+
+wrap_error! {
+    wrap XmlWriterError(quick_xml::Error) for DatError {
+        fn from (err) {
+            DatError::WriteError(format!("Error writing Logiqx XML: {}", err.0))
+        }
+    }
+}
+
+/// Writes game entries out as a Logiqx XML DAT, one at a time.
+///
+/// Serials are written as a comma-separated `serial` element, as in Redump DATs,
+/// and parents as the `cloneof` and `romof` attributes. Reading the written DAT back
+/// with `FromGeneric` yields the same names, ROMs, serials and parents, but not the
+/// source or any information parsed from the name of the entry.
+pub struct DatWriter<W: Write> {
+    writer: Writer<W>,
+}
+
+impl<W: Write> DatWriter<W> {
+    /// Starts a new datafile, writing the XML declaration, the Logiqx DOCTYPE, and the header if any.
+    pub fn new(inner: W, header: Option<&DatHeader>) -> Result<DatWriter<W>> {
+        let mut writer = DatWriter {
+            writer: Writer::new_with_indent(inner, b'\t', 1),
+        };
+        writer.write_start(header).map_err(XmlWriterError)?;
+        Ok(writer)
+    }
+
+    /// Writes a single game entry as a `game` element.
+    pub fn write_entry(&mut self, entry: &GameEntry) -> Result<()> {
+        self.write_entry_with_roms(entry, entry.rom_entries())
+    }
+
+    /// Writes a game entry as a `game` element with only the given ROMs,
+    /// such as the ROMs of the entry that are missing from a collection.
+    pub fn write_entry_with_roms<'a, I: IntoIterator<Item = &'a RomEntry>>(
+        &mut self,
+        entry: &GameEntry,
+        roms: I,
+    ) -> Result<()> {
+        Ok(self.write_game(entry, roms).map_err(XmlWriterError)?)
+    }
+
+    /// Closes the datafile, returning the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.writer
+            .write_event(Event::End(BytesEnd::borrowed(b"datafile")))
+            .map_err(XmlWriterError)?;
+        Ok(self.writer.into_inner())
+    }
+
+    fn write_start(&mut self, header: Option<&DatHeader>) -> quick_xml::Result<()> {
+        self.writer
+            .write_event(Event::Decl(BytesDecl::new(b"1.0", None, None)))?;
+        self.writer
+            .write_event(Event::DocType(BytesText::from_escaped_str(DOCTYPE)))?;
+        self.writer
+            .write_event(Event::Start(BytesStart::borrowed_name(b"datafile")))?;
+
+        let header = match header {
+            Some(header) => header,
+            None => return Ok(()),
+        };
+
+        self.writer
+            .write_event(Event::Start(BytesStart::borrowed_name(b"header")))?;
+        for (name, value) in [
+            ("name", header.name()),
+            ("description", header.description()),
+            ("version", header.version()),
+            ("date", header.date()),
+            ("author", header.author()),
+            ("homepage", header.homepage()),
+            ("url", header.url()),
+            ("comment", header.comment()),
+        ]
+        .iter()
+        {
+            if let Some(value) = value {
+                self.write_text_element(name, value)?;
+            }
+        }
+
+        if let Some(options) = header.clrmamepro() {
+            self.write_empty_element(
+                "clrmamepro",
+                &[
+                    ("header", options.header()),
+                    ("forcemerging", options.force_merging()),
+                    ("forcenodump", options.force_nodump()),
+                    ("forcepacking", options.force_packing()),
+                ],
+            )?;
+        }
+
+        if let Some(options) = header.romcenter() {
+            self.write_empty_element(
+                "romcenter",
+                &[
+                    ("plugin", options.plugin()),
+                    ("rommode", options.rom_mode()),
+                    ("biosmode", options.bios_mode()),
+                    ("samplemode", options.sample_mode()),
+                ],
+            )?;
+        }
+
+        self.writer
+            .write_event(Event::End(BytesEnd::borrowed(b"header")))
+    }
+
+    fn write_game<'a, I: IntoIterator<Item = &'a RomEntry>>(
+        &mut self,
+        entry: &GameEntry,
+        roms: I,
+    ) -> quick_xml::Result<()> {
+        let mut game = BytesStart::borrowed_name(b"game");
+        game.push_attribute(("name", entry.entry_name()));
+        if let Some(parent_name) = entry.parent_name() {
+            game.push_attribute(("cloneof", parent_name));
+        }
+        if let Some(rom_of) = entry.rom_of() {
+            game.push_attribute(("romof", rom_of));
+        }
+        self.writer.write_event(Event::Start(game))?;
+        self.write_text_element("description", entry.entry_name())?;
+        if !entry.serials().is_empty() {
+            let serials = entry
+                .serials()
+                .iter()
+                .map(|s| s.as_ref())
+                .collect::<Vec<_>>()
+                .join(", ");
+            self.write_text_element("serial", &serials)?;
+        }
+
+        for rom in roms {
+            let size = rom.size().to_string();
+            let crc = rom.hash_crc().map(|h| h.to_string());
+            let md5 = rom.hash_md5().map(|h| h.to_string());
+            let sha1 = rom.hash_sha1().map(|h| h.to_string());
+            let sha256 = rom.hash_sha256().map(|h| h.to_string());
+            let xxh3 = rom.hash_xxh3().map(|h| h.to_string());
+            // Good ROMs are written without a status, as they are in most DATs.
+            let status: Option<&str> = match rom.status() {
+                RomStatus::Good => None,
+                status => Some((&status).into()),
+            };
+            self.write_empty_element(
+                "rom",
+                &[
+                    ("name", Some(rom.file_name())),
+                    ("size", Some(&size)),
+                    ("crc", crc.as_deref()),
+                    ("md5", md5.as_deref()),
+                    ("sha1", sha1.as_deref()),
+                    ("sha256", sha256.as_deref()),
+                    ("xxh3", xxh3.as_deref()),
+                    ("status", status),
+                ],
+            )?;
+        }
+
+        self.writer
+            .write_event(Event::End(BytesEnd::borrowed(b"game")))
+    }
+
+    fn write_text_element(&mut self, name: &str, text: &str) -> quick_xml::Result<()> {
+        self.writer
+            .write_event(Event::Start(BytesStart::borrowed_name(name.as_bytes())))?;
+        self.writer
+            .write_event(Event::Text(BytesText::from_plain_str(text)))?;
+        self.writer
+            .write_event(Event::End(BytesEnd::borrowed(name.as_bytes())))
+    }
+
+    fn write_empty_element(&mut self, name: &str, attributes: &[(&str, Option<&str>)]) -> quick_xml::Result<()> {
+        let mut element = BytesStart::borrowed_name(name.as_bytes());
+        for (key, value) in attributes {
+            if let Some(value) = value {
+                element.push_attribute((*key, *value));
+            }
+        }
+        self.writer.write_event(Event::Empty(element))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DatWriter;
+    use crate::generic::FromGeneric;
+    use crate::nointro::FromNoIntro;
+    use crate::{GameEntry, RomStatus};
+
+    const NOINTRO_DAT: &str = r#"<?xml version="1.0"?>
+<datafile>
+    <header>
+        <name>Nintendo - Game Boy</name>
+        <version>20200101-000000</version>
+        <homepage>No-Intro</homepage>
+        <clrmamepro forcenodump="required"/>
+    </header>
+    <game name="Tetris (World) (Rev A)">
+        <description>Tetris (World) (Rev A)</description>
+        <rom name="Tetris (World) (Rev A).gb" size="32768" crc="46DF91AD" md5="982ED5D2B12A0377EB14BCDC4123744E" sha1="74591CC9501AF93873F9A5D3EB12DA12C0723BBC"/>
+    </game>
+</datafile>"#;
+
+    #[test]
+    fn writes_parsable_dat() {
+        let dat = GameEntry::try_from_nointro_str(NOINTRO_DAT).unwrap();
+        let mut writer = DatWriter::new(Vec::new(), dat.header()).unwrap();
+        for entry in dat.entries() {
+            writer.write_entry(entry.as_ref().unwrap()).unwrap();
+        }
+        let written = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert!(written.contains("<!DOCTYPE datafile PUBLIC"));
+
+        let reparsed = GameEntry::try_from_nointro_str(&written).unwrap();
+        assert_eq!(dat.header(), reparsed.header());
+        let entry = reparsed.entries()[0].as_ref().unwrap();
+        assert_eq!("Tetris (World) (Rev A)", entry.entry_name());
+        assert_eq!("46df91ad", entry.rom_entries()[0].hash_crc().unwrap().to_string());
+        assert_eq!(32768, entry.rom_entries()[0].size());
+    }
+
+    fn write_all(dat: &crate::ParsedDat) -> String {
+        let mut writer = DatWriter::new(Vec::new(), dat.header()).unwrap();
+        for entry in dat.entries() {
+            writer.write_entry(entry.as_ref().unwrap()).unwrap();
+        }
+        String::from_utf8(writer.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn round_trips_generic_entries() {
+        let dat = r#"<?xml version="1.0"?>
+<datafile>
+    <header>
+        <name>Sony - PlayStation</name>
+        <author>redump.org</author>
+    </header>
+    <game name="Ridge Racer (USA)">
+        <description>Ridge Racer (USA)</description>
+        <serial>SCUS-94300, SCUS-94300GH</serial>
+        <rom name="Ridge Racer (USA) (Track 1).bin" size="71299344" crc="C2E2ABA4" md5="5A0C5B8C2A6A2B8A6A38E3A2EBB4F9A6" sha1="EC0A2A4A1D2F3F8B8A4E1E5C2B3D8F8E6A5D4C3B"/>
+        <rom name="Ridge Racer (USA) (Track 2).bin" size="30752208" crc="1A2B3C4D" md5="6B1C5B8C2A6A2B8A6A38E3A2EBB4F9A6" sha1="FD0A2A4A1D2F3F8B8A4E1E5C2B3D8F8E6A5D4C3B"/>
+    </game>
+    <game name="Ridge Racer (Europe)" cloneof="Ridge Racer (USA)" romof="Ridge Racer (USA)">
+        <description>Ridge Racer (Europe)</description>
+        <rom name="Ridge Racer (Europe).bin" size="71299344" crc="D3E2ABA4" md5="7C2C5B8C2A6A2B8A6A38E3A2EBB4F9A6" sha1="0E1A2A4A1D2F3F8B8A4E1E5C2B3D8F8E6A5D4C3B"/>
+    </game>
+</datafile>"#;
+        let dat = GameEntry::try_from_str(dat).unwrap();
+        let reparsed = GameEntry::try_from_str(&write_all(&dat)).unwrap();
+        assert_eq!(dat.header(), reparsed.header());
+        assert_eq!(2, reparsed.entries().len());
+        for (entry, reparsed) in dat.entries().iter().zip(reparsed.entries()) {
+            let (entry, reparsed) = (entry.as_ref().unwrap(), reparsed.as_ref().unwrap());
+            assert_eq!(entry, reparsed);
+            for (rom, reparsed) in entry.rom_entries().iter().zip(reparsed.rom_entries()) {
+                assert_eq!(rom.file_name(), reparsed.file_name());
+                assert_eq!(rom.size(), reparsed.size());
+            }
+        }
+        assert_eq!(2, reparsed.entries()[0].as_ref().unwrap().serials().len());
+    }
+
+    #[test]
+    fn round_trips_nointro_roms() {
+        let dat = NOINTRO_DAT.replace(
+            r#"md5="982ED5D2B12A0377EB14BCDC4123744E""#,
+            r#"md5="982ED5D2B12A0377EB14BCDC4123744E" serial="DMG-TRA-1" sha256="0D3B5E0B4F5D1B1C5E6A7F8E9D0C1B2A3F4E5D6C7B8A9F0E1D2C3B4A5F6E7D8C" xxh3="8A2F3B9C1D4E5F60""#,
+        );
+        let dat = GameEntry::try_from_nointro_str(&dat).unwrap();
+        let reparsed = GameEntry::try_from_str(&write_all(&dat)).unwrap();
+        let entry = dat.entries()[0].as_ref().unwrap();
+        let reparsed = reparsed.entries()[0].as_ref().unwrap();
+        assert_eq!(entry.entry_name(), reparsed.entry_name());
+        assert_eq!(entry.rom_entries(), reparsed.rom_entries());
+        assert_eq!(entry.rom_entries()[0].file_name(), reparsed.rom_entries()[0].file_name());
+        assert_eq!(entry.serials(), reparsed.serials());
+        assert_eq!(
+            "0d3b5e0b4f5d1b1c5e6a7f8e9d0c1b2a3f4e5d6c7b8a9f0e1d2c3b4a5f6e7d8c",
+            reparsed.rom_entries()[0].hash_sha256().unwrap().to_string()
+        );
+        assert_eq!("8a2f3b9c1d4e5f60", reparsed.rom_entries()[0].hash_xxh3().unwrap().to_string());
+        assert_eq!("Generic", reparsed.source());
+    }
+
+    #[test]
+    fn writes_renamed_header() {
+        let dat = GameEntry::try_from_nointro_str(NOINTRO_DAT).unwrap();
+        let header = dat.header().unwrap().renamed("Fix Nintendo - Game Boy", "Missing from Nintendo - Game Boy");
+        let writer = DatWriter::new(Vec::new(), Some(&header)).unwrap();
+        let written = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+        let reparsed = GameEntry::try_from_nointro_str(&written).unwrap();
+        let reparsed = reparsed.header().unwrap();
+        assert_eq!(Some("Fix Nintendo - Game Boy"), reparsed.name());
+        assert_eq!(Some("Missing from Nintendo - Game Boy"), reparsed.description());
+        assert_eq!(Some("20200101-000000"), reparsed.version());
+        assert_eq!(Some("No-Intro"), reparsed.homepage());
+    }
+
+    #[test]
+    fn writes_only_given_roms() {
+        let dat = NOINTRO_DAT.replace(
+            "</game>",
+            r#"<rom name="Tetris (World) (Rev A).sav" size="8192" crc="00000000"/></game>"#,
+        );
+        let dat = GameEntry::try_from_nointro_str(&dat).unwrap();
+        let entry = dat.entries()[0].as_ref().unwrap();
+        let mut writer = DatWriter::new(Vec::new(), None).unwrap();
+        writer
+            .write_entry_with_roms(entry, entry.rom_entries().iter().skip(1))
+            .unwrap();
+        let written = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert!(!written.contains(r#"name="Tetris (World) (Rev A).gb""#));
+        assert!(written.contains(r#"name="Tetris (World) (Rev A).sav""#));
+    }
+
+    #[test]
+    fn round_trips_rom_status() {
+        let dat = NOINTRO_DAT.replace(
+            "</game>",
+            r#"<rom name="Tetris (World) (Rev A).sav" size="8192" status="nodump"/></game>"#,
+        );
+        let dat = GameEntry::try_from_nointro_str(&dat).unwrap();
+        let written = write_all(&dat);
+        assert!(written.contains(r#"status="nodump""#));
+        assert!(!written.contains(r#"status="good""#));
+
+        let reparsed = GameEntry::try_from_str(&written).unwrap();
+        let rewritten = GameEntry::try_from_str(&write_all(&reparsed)).unwrap();
+        assert_eq!(reparsed.entries()[0].as_ref().unwrap(), rewritten.entries()[0].as_ref().unwrap());
+        let roms = reparsed.entries()[0].as_ref().unwrap().rom_entries();
+        assert_eq!(RomStatus::Good, roms[0].status());
+        assert_eq!(RomStatus::NoDump, roms[1].status());
+    }
+}
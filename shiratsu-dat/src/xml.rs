@@ -1,87 +1,270 @@
 use crate::error::{DatError, Result as DatResult};
-use quick_xml::de::{from_reader as from_xml_buf, from_str as from_xml, DeError};
+use crate::{DatHeader, GameEntry};
+use quick_xml::de::{from_reader as from_xml_buf, DeError};
+use quick_xml::events::Event;
+use quick_xml::{Reader, Writer};
 use serde::de::DeserializeOwned;
-use serde::Deserialize;
+use std::convert::TryInto;
 use std::io::BufRead;
 
-#[derive(Debug, Deserialize, PartialEq)]
-struct Header {
-    homepage: Option<String>,
+/// Reads `game` elements out of a Logiqx XML DAT, one at a time.
+pub(crate) struct XmlReader<R: BufRead> {
+    reader: Reader<R>,
+    buf: Vec<u8>,
+    pending: Option<Vec<u8>>,
+    parse_game: fn(&[u8]) -> DatResult<GameEntry>,
+    map_err: fn(DeError) -> DatError,
+    done: bool,
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
-pub(super) struct Datfile<T> {
-    pub(super) game: Vec<T>,
-    header: Option<Header>,
-}
+impl<R: BufRead> XmlReader<R> {
+    pub(crate) fn new<
+        G: DeserializeOwned + TryInto<GameEntry, Error = DatError>,
+        E: Into<DatError> + From<DeError>,
+    >(
+        f: R,
+    ) -> XmlReader<R> {
+        let mut reader = Reader::from_reader(f);
+        reader.trim_text(true);
+
+        XmlReader {
+            reader,
+            buf: Vec::new(),
+            pending: None,
+            parse_game: parse_game::<G, E>,
+            map_err: map_err::<E>,
+            done: false,
+        }
+    }
 
-pub(super) fn parse_dat<G: PartialEq + DeserializeOwned, E: Into<DatError> + From<DeError>>(
-    f: &str,
-    expect_homepage: Option<&'static str>,
-) -> DatResult<Datfile<G>> {
-    parse_dat_unchecked::<G, E>(f).and_then(|e| {
-        if let (None, &None) = (expect_homepage, &e.header)
-        {
-           return Ok(e);
-        } else if let (None, &Some(&None)) = (expect_homepage, &e.header.as_ref().map(|header| &header.homepage)){
-            return Ok(e);
-        } else if let Some(expected) = expect_homepage {
-            if expected == e.header.as_ref().unwrap().homepage.as_deref().unwrap() {
-                return Ok(e);
+    /// Reads up to the end of the `header` element.
+    ///
+    /// If a `game` element is encountered first, it is kept to be yielded first.
+    pub(crate) fn read_header(&mut self) -> DatResult<Option<DatHeader>> {
+        self.read_header_element().map_err(self.map_err)
+    }
+
+    fn read_header_element(&mut self) -> Result<Option<DatHeader>, DeError> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_event(&mut self.buf)? {
+                Event::Start(e) if e.name() == b"header" => {
+                    let header = read_element(&mut self.reader, Event::Start(e.into_owned()))?;
+                    return Ok(Some(from_xml_buf(&header[..])?));
+                }
+                Event::Empty(e) if e.name() == b"header" => return Ok(Some(DatHeader::default())),
+                Event::Start(e) if e.name() == b"game" => {
+                    self.pending = Some(read_element(&mut self.reader, Event::Start(e.into_owned()))?);
+                    return Ok(None);
+                }
+                Event::Empty(e) if e.name() == b"game" => {
+                    self.pending = Some(read_element(&mut self.reader, Event::Empty(e.into_owned()))?);
+                    return Ok(None);
+                }
+                Event::Eof => return Ok(None),
+                _ => {}
             }
         }
-        Err(DatError::HeaderMismatchError(
-            expect_homepage.unwrap(),
-            e.header.map(|h| h.homepage).flatten(),
-        ))
-    })
+    }
+
+    /// Reads the next `game` element, returning its serialized XML.
+    fn read_game(&mut self) -> Result<Option<Vec<u8>>, DeError> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_event(&mut self.buf)? {
+                Event::Start(e) if e.name() == b"game" => {
+                    return Ok(Some(read_element(&mut self.reader, Event::Start(e.into_owned()))?));
+                }
+                Event::Empty(e) if e.name() == b"game" => {
+                    return Ok(Some(read_element(&mut self.reader, Event::Empty(e.into_owned()))?));
+                }
+                Event::Eof => return Ok(None),
+                _ => {}
+            }
+        }
+    }
 }
 
-pub(super) fn parse_dat_unchecked<
-    G: PartialEq + DeserializeOwned,
-    E: Into<DatError> + From<DeError>,
->(
-    f: &str,
-) -> DatResult<Datfile<G>> {
-    let d: Result<Datfile<G>, E> = from_xml(f).map_err::<E, _>(|e| e.into());
-    d.map_err(|e| e.into())
+impl<R: BufRead> Iterator for XmlReader<R> {
+    type Item = DatResult<GameEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(game) = self.pending.take() {
+            return Some((self.parse_game)(&game));
+        }
+        if self.done {
+            return None;
+        }
+        match self.read_game() {
+            Ok(Some(game)) => Some((self.parse_game)(&game)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err((self.map_err)(err)))
+            }
+        }
+    }
 }
 
-pub(super) fn parse_dat_buf<
-    R: BufRead,
-    G: PartialEq + DeserializeOwned,
-    E: Into<DatError> + From<DeError>,
->(
-    f: R,
-    expect_homepage: Option<&'static str>,
-) -> DatResult<Datfile<G>> {
-    parse_dat_unchecked_buf::<R, G, E>(f).and_then(|e| {
-        if let (None, &None) = (expect_homepage, &e.header)
-        {
-           return Ok(e);
-        } else if let (None, &Some(&None)) = (expect_homepage, &e.header.as_ref().map(|header| &header.homepage)){
-            return Ok(e);
-        } else if let Some(expected) = expect_homepage {
-            if let Some(homepage) = &e.header.as_ref().and_then(|header| header.homepage.as_deref()) {
-                if expected == *homepage {
-                    return Ok(e);
-                }
+/// Re-serializes the element beginning with `start` so that it can be deserialized on its own.
+fn read_element<R: BufRead>(reader: &mut Reader<R>, start: Event<'static>) -> Result<Vec<u8>, DeError> {
+    let is_empty = matches!(start, Event::Empty(_));
+    let mut writer = Writer::new(Vec::new());
+    writer.write_event(start)?;
+    if is_empty {
+        return Ok(writer.into_inner());
+    }
+
+    let mut buf = Vec::new();
+    let mut depth = 0usize;
+    loop {
+        let event = reader.read_event(&mut buf)?;
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) if depth == 0 => {
+                writer.write_event(event)?;
+                return Ok(writer.into_inner());
             }
+            Event::End(_) => depth -= 1,
+            Event::Eof => return Err(DeError::Eof),
+            _ => {}
         }
-        Err(DatError::HeaderMismatchError(
-            expect_homepage.unwrap(),
-            e.header.map(|h| h.homepage).flatten(),
-        ))
-    })
+        writer.write_event(event)?;
+        buf.clear();
+    }
 }
 
-pub(super) fn parse_dat_unchecked_buf<
-    R: BufRead,
-    G: PartialEq + DeserializeOwned,
+fn parse_game<
+    G: DeserializeOwned + TryInto<GameEntry, Error = DatError>,
     E: Into<DatError> + From<DeError>,
 >(
-    f: R,
-) -> DatResult<Datfile<G>> {
-    let d: Result<Datfile<G>, E> = from_xml_buf(f).map_err::<E, _>(|e| e.into());
-    d.map_err(|e| e.into())
+    game: &[u8],
+) -> DatResult<GameEntry> {
+    let game: G = from_xml_buf(game).map_err(map_err::<E>)?;
+    game.try_into()
+}
+
+fn map_err<E: Into<DatError> + From<DeError>>(err: DeError) -> DatError {
+    E::from(err).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nointro::FromNoIntro;
+    use crate::tosec::FromTOSEC;
+    use crate::{DatError, GameEntry, RomStatus};
+
+    const NOINTRO_DAT: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE datafile PUBLIC "-//Logiqx//DTD ROM Management Datafile//EN" "http://www.logiqx.com/Dats/datafile.dtd">
+<datafile>
+    <header>
+        <name>Nintendo - Game Boy</name>
+        <description>Nintendo - Game Boy</description>
+        <version>20200101-000000</version>
+        <homepage>No-Intro</homepage>
+        <clrmamepro forcenodump="required"/>
+    </header>
+    <game name="Tetris (World) (Rev A)">
+        <description>Tetris (World) (Rev A)</description>
+        <rom name="Tetris (World) (Rev A).gb" size="32768" crc="46DF91AD" md5="982ED5D2B12A0377EB14BCDC4123744E" sha1="74591CC9501AF93873F9A5D3EB12DA12C0723BBC"/>
+    </game>
+    <game name="Dr. Mario (World)">
+        <description>Dr. Mario (World)</description>
+        <rom name="Dr. Mario (World).gb" size="32768" crc="7D695C8C" md5="A2F8A3E0F7D0B7B8C1A7F2B7B2E2A6C1" sha1="ED2D4A7B3E6C2A4B9E2D1F2C3B4A5968778695A4"/>
+    </game>
+</datafile>"#;
+
+    #[test]
+    fn streams_games_in_order() {
+        let entries: Vec<GameEntry> = GameEntry::try_iter_from_nointro_buf(NOINTRO_DAT.as_bytes())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(2, entries.len());
+        assert_eq!("Tetris (World) (Rev A)", entries[0].entry_name());
+        assert_eq!("46df91ad", entries[0].rom_entries()[0].hash_crc().unwrap().to_string());
+        assert_eq!("Dr. Mario (World)", entries[1].entry_name());
+    }
+
+    #[test]
+    fn reads_full_header() {
+        let dat = GameEntry::try_from_nointro_str(NOINTRO_DAT).unwrap();
+        let header = dat.header().unwrap();
+        assert_eq!(Some("Nintendo - Game Boy"), header.name());
+        assert_eq!(Some("20200101-000000"), header.version());
+        assert_eq!(Some("No-Intro"), header.homepage());
+        assert_eq!(None, header.author());
+        assert_eq!(Some("required"), header.clrmamepro().unwrap().force_nodump());
+        assert_eq!(2, dat.entries().len());
+    }
+
+    #[test]
+    fn reads_parent_clone_attributes() {
+        let dat = NOINTRO_DAT.replace(
+            r#"<game name="Dr. Mario (World)">"#,
+            r#"<game name="Dr. Mario (World)" cloneof="Tetris (World) (Rev A)" romof="Tetris (World) (Rev A)">"#,
+        );
+        let entries: Vec<GameEntry> = GameEntry::try_iter_from_nointro_buf(dat.as_bytes())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(None, entries[0].parent_name());
+        assert_eq!(Some("Tetris (World) (Rev A)"), entries[1].parent_name());
+        assert_eq!(Some("Tetris (World) (Rev A)"), entries[1].rom_of());
+    }
+
+    #[test]
+    fn checks_header_before_games() {
+        match GameEntry::try_iter_from_tosec_buf(NOINTRO_DAT.as_bytes()) {
+            Err(DatError::HeaderMismatchError("TOSEC", Some(homepage))) => assert_eq!("No-Intro", homepage),
+            _ => panic!("expected header mismatch"),
+        }
+        assert_eq!(2, GameEntry::try_unchecked_iter_from_tosec_buf(NOINTRO_DAT.as_bytes()).unwrap().count());
+    }
+
+    #[test]
+    fn yields_entry_errors_in_place() {
+        let dat = NOINTRO_DAT.replace(r#"size="32768" crc="7D695C8C""#, r#"size="large" crc="7D695C8C""#);
+        let entries: Vec<_> = GameEntry::try_iter_from_nointro_buf(dat.as_bytes()).unwrap().collect();
+        assert_eq!(2, entries.len());
+        assert!(entries[0].is_ok());
+        assert!(entries[1].is_err());
+    }
+
+    #[test]
+    fn reads_rom_status() {
+        let dat = NOINTRO_DAT
+            .replace(
+                r#"size="32768" crc="46DF91AD""#,
+                r#"size="32768" status="verified" crc="46DF91AD""#,
+            )
+            .replace(
+                r#"size="32768" crc="7D695C8C" md5="A2F8A3E0F7D0B7B8C1A7F2B7B2E2A6C1" sha1="ED2D4A7B3E6C2A4B9E2D1F2C3B4A5968778695A4""#,
+                r#"size="32768" status="nodump""#,
+            );
+        let entries: Vec<GameEntry> = GameEntry::try_iter_from_nointro_buf(dat.as_bytes())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(RomStatus::Verified, entries[0].rom_entries()[0].status());
+        let nodump = &entries[1].rom_entries()[0];
+        assert_eq!(RomStatus::NoDump, nodump.status());
+        assert_eq!(None, nodump.hash_crc());
+        assert_eq!(None, nodump.hash_sha1());
+    }
+
+    #[test]
+    fn rejects_roms_without_hashes() {
+        let dat = NOINTRO_DAT.replace(
+            r#"size="32768" crc="7D695C8C" md5="A2F8A3E0F7D0B7B8C1A7F2B7B2E2A6C1" sha1="ED2D4A7B3E6C2A4B9E2D1F2C3B4A5968778695A4""#,
+            r#"size="32768" status="baddump""#,
+        );
+        let entries: Vec<_> = GameEntry::try_iter_from_nointro_buf(dat.as_bytes()).unwrap().collect();
+        assert_eq!(2, entries.len());
+        assert!(entries[0].is_ok());
+        assert!(matches!(entries[1], Err(DatError::ParseError(_))));
+    }
 }
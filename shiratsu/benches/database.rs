@@ -0,0 +1,89 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use shiratsu_dat::generic::FromGeneric;
+use shiratsu_dat::GameEntry;
+use shiratsu_stone::PlatformId;
+use std::convert::TryInto;
+
+use shiratsu::database::ShiratsuDatabase;
+
+const GAMES: usize = 2000;
+
+fn make_entries() -> Vec<GameEntry> {
+    let mut dat = String::from("<datafile><header><name>Benchmark</name></header>");
+    for i in 0..GAMES {
+        dat.push_str(&format!(
+            r#"<game name="Game {0} (USA)"><description>Game {0} (USA)</description><serial>SLUS-{0:05}</serial>
+                <rom name="Game {0} (USA) (Track 1).bin" size="{0}" crc="{0:08x}" md5="{0:032x}" sha1="{0:040x}"/>
+                <rom name="Game {0} (USA) (Track 2).bin" size="{0}" crc="{1:08x}" md5="{1:032x}" sha1="{1:040x}"/>
+            </game>"#,
+            i,
+            i + GAMES
+        ));
+    }
+    dat.push_str("</datafile>");
+    GameEntry::try_from_str(&dat)
+        .unwrap()
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+fn new_database() -> (ShiratsuDatabase, i64) {
+    let mut db = ShiratsuDatabase::new().unwrap();
+    let dat_id = db.add_dat("benchmark.dat", None, "", "Generic").unwrap();
+    (db, dat_id)
+}
+
+fn insert_entries(c: &mut Criterion) {
+    let entries = make_entries();
+    let platform: &PlatformId = "NINTENDO_GB".try_into().unwrap();
+
+    let mut group = c.benchmark_group("insert_entries");
+    group.sample_size(10);
+    group.bench_function("add_entry_uncached", |b| {
+        b.iter_batched(
+            || {
+                let (db, dat_id) = new_database();
+                (db.with_statement_cache_capacity(0), dat_id)
+            },
+            |(mut db, dat_id)| {
+                for entry in entries.iter() {
+                    db.add_entry(entry, platform, dat_id).unwrap();
+                }
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.bench_function("add_entry", |b| {
+        b.iter_batched(
+            new_database,
+            |(mut db, dat_id)| {
+                for entry in entries.iter() {
+                    db.add_entry(entry, platform, dat_id).unwrap();
+                }
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.bench_function("add_entries", |b| {
+        b.iter_batched(
+            new_database,
+            |(mut db, dat_id)| db.add_entries(&entries, platform, dat_id, |_, _| {}).unwrap(),
+            BatchSize::PerIteration,
+        )
+    });
+    group.bench_function("add_entries_batched", |b| {
+        b.iter_batched(
+            || {
+                let (db, dat_id) = new_database();
+                (db.with_batch_size(100), dat_id)
+            },
+            |(mut db, dat_id)| db.add_entries(&entries, platform, dat_id, |_, _| {}).unwrap(),
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, insert_entries);
+criterion_main!(benches);
@@ -0,0 +1,192 @@
+use sevenz_rust::{Password, SevenZReader};
+use zip::ZipArchive;
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum ArchiveKind {
+    Zip,
+    SevenZip,
+}
+
+fn archive_kind(path: &Path) -> Option<ArchiveKind> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(e) if e.eq_ignore_ascii_case("zip") => Some(ArchiveKind::Zip),
+        Some(e) if e.eq_ignore_ascii_case("7z") => Some(ArchiveKind::SevenZip),
+        _ => None,
+    }
+}
+
+/// Whether the path is a zip or 7z archive that DATs can be read from.
+pub fn is_archive(path: &Path) -> bool {
+    archive_kind(path).is_some()
+}
+
+/// Streams every file in the archive to `each` in the order they are stored, along with its name.
+///
+/// Every file is visited in a single pass, since files in a solid 7z archive can not be
+/// decompressed without decompressing every file before them.
+pub fn for_each_file<F>(path: &Path, mut each: F) -> Result<()>
+where
+    F: FnMut(&str, &mut dyn Read) -> Result<()>,
+{
+    match archive_kind(path) {
+        Some(ArchiveKind::Zip) => {
+            let mut archive = ZipArchive::new(File::open(path)?)?;
+            for i in 0..archive.len() {
+                let mut file = archive.by_index(i)?;
+                if !file.is_dir() {
+                    let name = file.name().to_string();
+                    each(&name, &mut file)?;
+                }
+            }
+            Ok(())
+        }
+        Some(ArchiveKind::SevenZip) => {
+            let mut archive = SevenZReader::open(path, Password::empty())?;
+            // The callback can only fail with a sevenz_rust error, so an error from `each` is kept aside.
+            let mut result = Ok(());
+            archive.for_each_entries(|file, reader| {
+                if !file.is_directory() {
+                    if let Err(err) = each(file.name(), reader) {
+                        result = Err(err);
+                        return Ok(false);
+                    }
+                }
+                // The rest of the file must be read before the next file in the block.
+                io::copy(reader, &mut io::sink())?;
+                Ok(true)
+            })?;
+            result
+        }
+        None => Err(anyhow!("{} is not a zip or 7z archive.", path.display())),
+    }
+}
+
+/// Lists the names of the files in the archive that match the filter, in the order they are stored,
+/// without decompressing any of them.
+pub fn file_names<F: Fn(&str) -> bool>(path: &Path, filter: F) -> Result<Vec<String>> {
+    let names = match archive_kind(path) {
+        Some(ArchiveKind::Zip) => {
+            let archive = ZipArchive::new(File::open(path)?)?;
+            let names = archive.file_names().filter(|name| !name.ends_with('/') && filter(name));
+            names.map(String::from).collect()
+        }
+        Some(ArchiveKind::SevenZip) => {
+            let archive = SevenZReader::open(path, Password::empty())?;
+            let files = archive.archive().files.iter().filter(|file| !file.is_directory());
+            files.map(|file| file.name()).filter(|name| filter(name)).map(String::from).collect()
+        }
+        None => return Err(anyhow!("{} is not a zip or 7z archive.", path.display())),
+    };
+    Ok(names)
+}
+
+/// Streams the file with the given name in the archive to `read`.
+///
+/// Files in a zip archive are read directly, but the files stored before it in a solid 7z
+/// archive have to be decompressed first.
+pub fn read_file<T, F>(path: &Path, name: &str, read: F) -> Result<T>
+where
+    F: FnOnce(&mut dyn Read) -> Result<T>,
+{
+    match archive_kind(path) {
+        Some(ArchiveKind::Zip) => {
+            let mut archive = ZipArchive::new(File::open(path)?)?;
+            let mut file = archive.by_name(name)?;
+            read(&mut file)
+        }
+        Some(ArchiveKind::SevenZip) => {
+            let mut archive = SevenZReader::open(path, Password::empty())?;
+            let mut read = Some(read);
+            let mut result = None;
+            archive.for_each_entries(|file, reader| {
+                if file.name() == name {
+                    if let Some(read) = read.take() {
+                        result = Some(read(reader));
+                    }
+                    return Ok(false);
+                }
+                io::copy(reader, &mut io::sink())?;
+                Ok(true)
+            })?;
+            result.unwrap_or_else(|| Err(anyhow!("{} is not in {}.", name, path.display())))
+        }
+        None => Err(anyhow!("{} is not a zip or 7z archive.", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sevenz_rust::{SevenZArchiveEntry, SevenZWriter, SourceReader};
+    use std::io::Write;
+    use zip::write::FileOptions;
+    use zip::{CompressionMethod, ZipWriter};
+
+    fn read_to_end(path: &Path, name: &str) -> Vec<u8> {
+        read_file(path, name, |reader| {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+            Ok(data)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn reads_zip_archives() {
+        let path = std::env::temp_dir().join(format!("shiratsu-archive-{}.zip", std::process::id()));
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        zip.add_directory("No-Intro/", options).unwrap();
+        zip.start_file("No-Intro/Nintendo - Game Boy (20200101-000000).dat", options)
+            .unwrap();
+        zip.write_all(b"<datafile/>").unwrap();
+        zip.start_file("README.txt", options).unwrap();
+        zip.write_all(b"readme").unwrap();
+        zip.finish().unwrap();
+
+        assert!(is_archive(&path));
+        let names = file_names(&path, |name| name.ends_with(".dat")).unwrap();
+        assert_eq!(vec!["No-Intro/Nintendo - Game Boy (20200101-000000).dat"], names);
+        assert_eq!(b"<datafile/>".to_vec(), read_to_end(&path, &names[0]));
+        assert!(read_file(&path, "missing.dat", |_| Ok(())).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reads_solid_7z_archives() {
+        let path = std::env::temp_dir().join(format!("shiratsu-archive-{}.7z", std::process::id()));
+        let files: &[(&str, &[u8])] = &[
+            ("Nintendo - Game Boy (20200101-000000).dat", b"<datafile>gb</datafile>"),
+            ("README.txt", b"readme"),
+            ("Nintendo - Game Boy Advance (20200101-000000).dat", b"<datafile>gba</datafile>"),
+        ];
+        let mut writer = SevenZWriter::create(&path).unwrap();
+        let entries = files
+            .iter()
+            .map(|(name, _)| {
+                let mut entry = SevenZArchiveEntry::new();
+                entry.name = name.to_string();
+                entry.has_stream = true;
+                entry
+            })
+            .collect();
+        let readers: Vec<_> = files.iter().map(|(_, data)| SourceReader::new(*data)).collect();
+        writer.push_archive_entries(entries, readers.into()).unwrap();
+        writer.finish().unwrap();
+
+        assert!(is_archive(&path));
+        let names = file_names(&path, |name| name.ends_with(".dat")).unwrap();
+        assert_eq!(vec![files[0].0, files[2].0], names);
+        for (name, data) in [files[2], files[0]].iter() {
+            assert_eq!(data.to_vec(), read_to_end(&path, name));
+        }
+        assert!(read_file(&path, "missing.dat", |_| Ok(())).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
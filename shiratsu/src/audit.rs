@@ -0,0 +1,459 @@
+use crc32fast::Hasher as Crc32;
+use md5::{Digest, Md5};
+use rusqlite::{params, Connection, OpenFlags};
+use sha1::Sha1;
+use sha2::Sha256;
+use walkdir::WalkDir;
+use xxhash_rust::xxh3::Xxh3;
+use zip::ZipArchive;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+/// The hashes of a local file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FileHashes {
+    pub crc: String,
+    pub md5: String,
+    pub sha1: String,
+    pub sha256: String,
+    pub xxh3: String,
+    pub size: i64,
+}
+
+/// Hashes everything read from the reader in a single pass.
+pub fn hash_reader<R: Read>(mut reader: R) -> io::Result<FileHashes> {
+    let mut crc = Crc32::new();
+    let mut md5 = Md5::new();
+    let mut sha1 = Sha1::new();
+    let mut sha256 = Sha256::new();
+    let mut xxh3 = Xxh3::new();
+    let mut size = 0;
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        match reader.read(&mut buf)? {
+            0 => break,
+            n => {
+                crc.update(&buf[..n]);
+                md5.update(&buf[..n]);
+                sha1.update(&buf[..n]);
+                sha256.update(&buf[..n]);
+                xxh3.update(&buf[..n]);
+                size += n as i64;
+            }
+        }
+    }
+    Ok(FileHashes {
+        crc: format!("{:08x}", crc.finalize()),
+        md5: format!("{:x}", md5.finalize()),
+        sha1: sha1.digest().to_string(),
+        sha256: format!("{:x}", sha256.finalize()),
+        xxh3: format!("{:016x}", xxh3.digest()),
+        size,
+    })
+}
+
+/// A file in the local collection, which may be a file inside a zip archive.
+#[derive(Debug, Clone)]
+pub struct LocalFile {
+    /// The path of the file, or of the zip archive the file is in.
+    pub path: PathBuf,
+    /// The name of the file inside the zip archive, if any.
+    pub inner: Option<String>,
+    pub hashes: FileHashes,
+}
+
+impl std::fmt::Display for LocalFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.inner {
+            Some(inner) => write!(f, "{}/{}", self.path.display(), inner),
+            None => write!(f, "{}", self.path.display()),
+        }
+    }
+}
+
+fn is_zip(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("zip"))
+        .unwrap_or(false)
+}
+
+/// Hashes every file inside the zip archive.
+fn scan_zip(path: &Path) -> Result<Vec<LocalFile>> {
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+    let mut files = Vec::new();
+    for i in 0..archive.len() {
+        let file = archive.by_index(i)?;
+        if file.is_dir() {
+            continue;
+        }
+        let inner = file.name().to_string();
+        files.push(LocalFile {
+            path: path.to_path_buf(),
+            inner: Some(inner),
+            hashes: hash_reader(file)?,
+        });
+    }
+    Ok(files)
+}
+
+/// Hashes every file under the directory, including files inside zip archives.
+///
+/// Files and archives that can not be read are passed to `on_unreadable` and left out,
+/// rather than stopping the scan.
+pub fn scan_dir<T, F, G>(dir: T, on_file: F, on_unreadable: G) -> Vec<LocalFile>
+where
+    T: AsRef<Path>,
+    F: Fn(&LocalFile),
+    G: Fn(&Path, &anyhow::Error),
+{
+    let mut files = Vec::new();
+    for entry in WalkDir::new(dir).into_iter().flatten() {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry.path();
+        let scanned = if is_zip(path) {
+            scan_zip(path)
+        } else {
+            File::open(path)
+                .and_then(hash_reader)
+                .map(|hashes| vec![LocalFile {
+                    path: path.to_path_buf(),
+                    inner: None,
+                    hashes,
+                }])
+                .map_err(anyhow::Error::new)
+        };
+        match scanned {
+            Ok(scanned) => {
+                for local in scanned {
+                    on_file(&local);
+                    files.push(local);
+                }
+            }
+            Err(err) => on_unreadable(path, &err),
+        }
+    }
+    files
+}
+
+/// A ROM known to the catalogue that the local collection is audited against.
+#[derive(Debug, Clone)]
+pub struct KnownRom {
+    pub platform_id: String,
+    pub entry_name: String,
+    pub file_name: String,
+    pub size: i64,
+    pub crc: Option<String>,
+    pub md5: Option<String>,
+    pub sha1: Option<String>,
+    pub sha256: Option<String>,
+    pub xxh3: Option<String>,
+}
+
+/// An index of known ROMs by their hashes.
+#[derive(Debug, Default)]
+pub struct RomIndex {
+    roms: Vec<KnownRom>,
+    by_sha256: HashMap<String, Vec<usize>>,
+    by_sha1: HashMap<String, Vec<usize>>,
+    by_md5: HashMap<String, Vec<usize>>,
+    by_xxh3: HashMap<String, Vec<usize>>,
+    by_crc: HashMap<(String, i64), Vec<usize>>,
+}
+
+impl RomIndex {
+    /// Indexes every ROM in the `rom` table of a shiragame database.
+    ///
+    /// ROMs that have never been dumped are left out, since no local file can match them.
+    pub fn from_database<T: AsRef<Path>>(path: T) -> Result<RomIndex> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let mut stmt = conn.prepare(
+            "SELECT game.platform_id, game.entry_name, rom.file_name, rom.size, rom.crc, rom.md5, rom.sha1,
+                rom.sha256, rom.xxh3
+            FROM rom INNER JOIN game ON rom.game_id = game.game_id
+            WHERE rom.status != 'nodump'
+            ORDER BY game.game_id",
+        )?;
+        let roms = stmt.query_map(params![], |row| {
+            Ok(KnownRom {
+                platform_id: row.get(0)?,
+                entry_name: row.get(1)?,
+                file_name: row.get(2)?,
+                size: row.get(3)?,
+                crc: row.get(4)?,
+                md5: row.get(5)?,
+                sha1: row.get(6)?,
+                sha256: row.get(7)?,
+                xxh3: row.get(8)?,
+            })
+        })?;
+
+        let mut index = RomIndex::default();
+        for rom in roms {
+            index.push(rom?);
+        }
+        Ok(index)
+    }
+
+    pub fn push(&mut self, rom: KnownRom) {
+        let i = self.roms.len();
+        if let Some(sha256) = &rom.sha256 {
+            self.by_sha256.entry(sha256.clone()).or_default().push(i);
+        }
+        if let Some(sha1) = &rom.sha1 {
+            self.by_sha1.entry(sha1.clone()).or_default().push(i);
+        }
+        if let Some(md5) = &rom.md5 {
+            self.by_md5.entry(md5.clone()).or_default().push(i);
+        }
+        if let Some(xxh3) = &rom.xxh3 {
+            self.by_xxh3.entry(xxh3.clone()).or_default().push(i);
+        }
+        if let Some(crc) = &rom.crc {
+            self.by_crc.entry((crc.clone(), rom.size)).or_default().push(i);
+        }
+        self.roms.push(rom);
+    }
+
+    pub fn roms(&self) -> &[KnownRom] {
+        &self.roms
+    }
+
+    /// Finds the indices of every known ROM matching the hashes, using the strongest hash available.
+    ///
+    /// The same file may be part of more than one game entry.
+    pub fn find(&self, hashes: &FileHashes) -> Vec<usize> {
+        let mut found = Vec::new();
+        for candidates in [
+            self.by_sha256.get(&hashes.sha256),
+            self.by_sha1.get(&hashes.sha1),
+            self.by_md5.get(&hashes.md5),
+            self.by_xxh3.get(&hashes.xxh3),
+            self.by_crc.get(&(hashes.crc.clone(), hashes.size)),
+        ]
+        .iter()
+        .flatten()
+        {
+            for &i in candidates.iter() {
+                if !found.contains(&i) && self.is_match(&self.roms[i], hashes) {
+                    found.push(i);
+                }
+            }
+        }
+        found
+    }
+
+    /// A known ROM matches if every hash both sides have are equal.
+    // `Option::is_none_or` needs a newer Rust than the crate otherwise does.
+    #[allow(clippy::unnecessary_map_or)]
+    fn is_match(&self, rom: &KnownRom, hashes: &FileHashes) -> bool {
+        rom.sha256.as_deref().map_or(true, |h| h == hashes.sha256)
+            && rom.sha1.as_deref().map_or(true, |h| h == hashes.sha1)
+            && rom.md5.as_deref().map_or(true, |h| h == hashes.md5)
+            && rom.xxh3.as_deref().map_or(true, |h| h == hashes.xxh3)
+            && rom.crc.as_deref().map_or(true, |h| h == hashes.crc && rom.size == hashes.size)
+    }
+}
+
+/// The result of auditing a local collection against a `RomIndex`.
+#[derive(Debug)]
+pub struct Audit {
+    pub files: Vec<LocalFile>,
+    /// For each known ROM, the indices of the local files that match it.
+    pub matches: Vec<Vec<usize>>,
+    /// The indices of the local files that did not match any known ROM.
+    pub unknown: Vec<usize>,
+}
+
+pub fn audit(index: &RomIndex, files: Vec<LocalFile>) -> Audit {
+    let mut matches = vec![Vec::new(); index.roms().len()];
+    let mut unknown = Vec::new();
+    for (file_index, file) in files.iter().enumerate() {
+        let found = index.find(&file.hashes);
+        if found.is_empty() {
+            unknown.push(file_index);
+        }
+        for rom_index in found {
+            matches[rom_index].push(file_index);
+        }
+    }
+    Audit {
+        files,
+        matches,
+        unknown,
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GameStatus {
+    Have,
+    Partial,
+    Missing,
+}
+
+impl AsRef<str> for GameStatus {
+    fn as_ref(&self) -> &str {
+        match self {
+            GameStatus::Have => "have",
+            GameStatus::Partial => "partial",
+            GameStatus::Missing => "missing",
+        }
+    }
+}
+
+/// The audit of a single game entry.
+#[derive(Debug)]
+pub struct GameAudit<'a> {
+    pub entry_name: &'a str,
+    /// The indices of the known ROMs of the game entry.
+    pub roms: Vec<usize>,
+    pub have: usize,
+}
+
+impl<'a> GameAudit<'a> {
+    pub fn status(&self) -> GameStatus {
+        match self.have {
+            0 => GameStatus::Missing,
+            n if n == self.roms.len() => GameStatus::Have,
+            _ => GameStatus::Partial,
+        }
+    }
+}
+
+/// The audit of a single platform.
+#[derive(Debug, Default)]
+pub struct PlatformAudit<'a> {
+    pub games: Vec<GameAudit<'a>>,
+}
+
+impl<'a> PlatformAudit<'a> {
+    pub fn count(&self, status: GameStatus) -> usize {
+        self.games.iter().filter(|g| g.status() == status).count()
+    }
+}
+
+impl Audit {
+    /// Groups the audit by platform and game entry, in the order the ROMs were indexed.
+    pub fn by_platform<'a>(&self, index: &'a RomIndex) -> BTreeMap<&'a str, PlatformAudit<'a>> {
+        let mut platforms: BTreeMap<&str, PlatformAudit> = BTreeMap::new();
+        for (i, rom) in index.roms().iter().enumerate() {
+            let platform = platforms.entry(&rom.platform_id).or_default();
+            let have = !self.matches[i].is_empty();
+            match platform.games.last_mut() {
+                Some(game) if game.entry_name == rom.entry_name => {
+                    game.roms.push(i);
+                    game.have += have as usize;
+                }
+                _ => platform.games.push(GameAudit {
+                    entry_name: &rom.entry_name,
+                    roms: vec![i],
+                    have: have as usize,
+                }),
+            }
+        }
+        platforms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rom(entry_name: &str, file_name: &str, data: &[u8]) -> KnownRom {
+        let hashes = hash_reader(data).unwrap();
+        KnownRom {
+            platform_id: "NINTENDO_GB".to_string(),
+            entry_name: entry_name.to_string(),
+            file_name: file_name.to_string(),
+            size: hashes.size,
+            crc: Some(hashes.crc),
+            md5: None,
+            sha1: Some(hashes.sha1),
+            sha256: Some(hashes.sha256),
+            xxh3: Some(hashes.xxh3),
+        }
+    }
+
+    fn local(name: &str, data: &[u8]) -> LocalFile {
+        LocalFile {
+            path: PathBuf::from(name),
+            inner: None,
+            hashes: hash_reader(data).unwrap(),
+        }
+    }
+
+    #[test]
+    fn hashes_known_values() {
+        let hashes = hash_reader(&b"shiratsu"[..]).unwrap();
+        assert_eq!(8, hashes.size);
+        assert_eq!("a75259c9", hashes.crc);
+        assert_eq!("cfcb472038b35cc7445d07f2ea9602ae", hashes.md5);
+        assert_eq!("c8c33ea8c054b63a1f76948cba1cf2d8379b315c", hashes.sha1);
+        assert_eq!(
+            "06bcd8afa3c3d5bdb1ac30fcf3e03b0838d581cfb5241217e5cb58180aaeca6e",
+            hashes.sha256
+        );
+        assert_eq!("add08ea7dfb3c669", hashes.xxh3);
+        assert_eq!("2d06800538d394c2", hash_reader(&b""[..]).unwrap().xxh3);
+    }
+
+    #[test]
+    fn audits_have_partial_missing_unknown() {
+        let mut index = RomIndex::default();
+        index.push(rom("Tetris (World)", "Tetris (World).gb", b"tetris"));
+        index.push(rom("Pokemon (USA)", "Pokemon (USA) (Track 1).bin", b"pokemon 1"));
+        index.push(rom("Pokemon (USA)", "Pokemon (USA) (Track 2).bin", b"pokemon 2"));
+        index.push(rom("Dr. Mario (World)", "Dr. Mario (World).gb", b"dr. mario"));
+
+        let audit = audit(
+            &index,
+            vec![
+                local("tetris.gb", b"tetris"),
+                local("pkmn.bin", b"pokemon 1"),
+                local("unknown.gb", b"unknown"),
+            ],
+        );
+        assert_eq!(vec![2], audit.unknown);
+
+        let platforms = audit.by_platform(&index);
+        let platform = &platforms["NINTENDO_GB"];
+        assert_eq!(GameStatus::Have, platform.games[0].status());
+        assert_eq!(GameStatus::Partial, platform.games[1].status());
+        assert_eq!(GameStatus::Missing, platform.games[2].status());
+        assert_eq!(1, platform.count(GameStatus::Have));
+    }
+
+    #[test]
+    fn scans_past_unreadable_archives() {
+        use std::cell::RefCell;
+        use std::io::Write;
+        use zip::write::FileOptions;
+        use zip::ZipWriter;
+
+        let dir = std::env::temp_dir().join(format!("shiratsu-scan-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("tetris.gb"), b"tetris").unwrap();
+        std::fs::write(dir.join("corrupt.zip"), b"not a zip archive").unwrap();
+        let mut zip = ZipWriter::new(File::create(dir.join("pokemon.zip")).unwrap());
+        zip.start_file("Pokemon (USA).gb", FileOptions::default()).unwrap();
+        zip.write_all(b"pokemon").unwrap();
+        zip.finish().unwrap();
+
+        let unreadable = RefCell::new(Vec::new());
+        let mut files = scan_dir(&dir, |_| {}, |path, _| unreadable.borrow_mut().push(path.to_path_buf()));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(vec![dir.join("corrupt.zip")], unreadable.into_inner());
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(2, files.len());
+        assert_eq!(Some("Pokemon (USA).gb"), files[0].inner.as_deref());
+        assert_eq!(hash_reader(&b"tetris"[..]).unwrap(), files[1].hashes);
+    }
+}
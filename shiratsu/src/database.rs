@@ -1,10 +1,10 @@
-use rusqlite::{backup::*, named_params, params, Connection, Result as SqliteResult};
+use rusqlite::{backup::*, named_params, params, Connection, OpenFlags, Result as SqliteResult};
 
 use shiratsu_stone::{
     PlatformId, StonePlatforms, find_mimetype
 };
 
-use shiratsu_dat::{DevelopmentStatus, GameEntry};
+use shiratsu_dat::{DatHeader, DevelopmentStatus, GameEntry};
 
 use shiratsu_naming::{
     region::Region,
@@ -18,10 +18,12 @@ use std::{io, io::ErrorKind};
 
 use uuid::Uuid;
 
-const SCHEMA_VERSION: &'static str = "3.0.0";
+const SCHEMA_VERSION: &'static str = "3.9.0";
 
 pub struct ShiratsuDatabase {
     memory_connection: Connection,
+    batch_size: Option<usize>,
+    selection: Option<String>,
 }
 
 #[derive(Debug)]
@@ -29,6 +31,7 @@ pub struct ShiratsuDatabase {
 pub enum DatabaseError {
     IOError(io::Error),
     SqliteError(rusqlite::Error),
+    SchemaVersionError(String),
 }
 
 impl std::fmt::Display for DatabaseError {
@@ -36,10 +39,17 @@ impl std::fmt::Display for DatabaseError {
         match self {
             DatabaseError::IOError(err) => write!(f, "Database IO Error: {}", err),
             DatabaseError::SqliteError(err) => write!(f, "SQLite Error: {}", err),
+            DatabaseError::SchemaVersionError(version) => write!(
+                f,
+                "Schema version {} can not be updated to {}, the database must be rebuilt",
+                version, SCHEMA_VERSION
+            ),
         }
     }
 }
 
+impl std::error::Error for DatabaseError {}
+
 impl From<rusqlite::Error> for DatabaseError {
     fn from(err: rusqlite::Error) -> Self {
         DatabaseError::SqliteError(err)
@@ -60,28 +70,216 @@ impl ShiratsuDatabase {
         create_database(&mut conn)?;
         Ok(ShiratsuDatabase {
             memory_connection: conn,
+            batch_size: None,
+            selection: None,
         })
     }
 
-    pub fn add_entry(&mut self, entry: &GameEntry, platform: &PlatformId) -> Result<()> {
-        insert_entry(entry, platform, &mut self.memory_connection)?;
+    /// Loads an existing database into memory, so that DATs can be removed and added before
+    /// it is saved over the original.
+    ///
+    /// The database must have been generated with the same schema version.
+    pub fn open<T: AsRef<Path>>(path: T) -> Result<ShiratsuDatabase> {
+        let file = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let schema_version: String =
+            file.query_row("SELECT schema_version FROM shiragame", params![], |row| row.get(0))?;
+        if schema_version != SCHEMA_VERSION {
+            return Err(DatabaseError::SchemaVersionError(schema_version));
+        }
+
+        let mut conn = Connection::open_in_memory()?;
+        Backup::new(&file, &mut conn)?.run_to_completion(5, Duration::new(0, 0), None)?;
+        let selection = file.query_row("SELECT selection FROM shiragame", params![], |row| row.get(0))?;
+        drop_indexes(&mut conn)?;
+        Ok(ShiratsuDatabase {
+            memory_connection: conn,
+            batch_size: None,
+            selection,
+        })
+    }
+
+    /// Records the 1G1R selection rules the entries of the database are selected with,
+    /// so that DATs added when the database is updated are selected with the same rules.
+    pub fn with_selection(mut self, rules: &str) -> Self {
+        self.selection = Some(rules.to_string());
+        self
+    }
+
+    /// The 1G1R selection rules the entries of the database are selected with, if any.
+    pub fn selection(&self) -> Option<&str> {
+        self.selection.as_deref()
+    }
+
+    /// Sets the number of entries `add_entries` inserts per transaction.
+    ///
+    /// By default, every entry passed to a single call of `add_entries` is inserted in one transaction.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = Some(batch_size.max(1));
+        self
+    }
+
+    /// Sets how many prepared statements are kept for reuse between inserts.
+    ///
+    /// With a capacity of 0, every statement is prepared again for every insert,
+    /// which is only useful to measure what caching them saves.
+    pub fn with_statement_cache_capacity(self, capacity: usize) -> Self {
+        self.memory_connection.set_prepared_statement_cache_capacity(capacity);
+        self
+    }
+
+    pub fn add_dat(
+        &mut self,
+        file_name: &str,
+        header: Option<&DatHeader>,
+        sha1: &str,
+        source: &str,
+    ) -> Result<i64> {
+        Ok(insert_dat(file_name, header, sha1, source, &mut self.memory_connection)?)
+    }
+
+    /// Adds a single entry in its own transaction.
+    pub fn add_entry(&mut self, entry: &GameEntry, platform: &PlatformId, dat_id: i64) -> Result<i64> {
+        let tx = self.memory_connection.transaction()?;
+        let game_id = insert_entry(entry, platform, dat_id, &tx)?;
+        tx.commit()?;
+        Ok(game_id)
+    }
+
+    /// Adds every entry, returning the game IDs of the entries in order.
+    ///
+    /// Entries are inserted in batches of the configured batch size, one transaction per batch.
+    /// `on_entry` is called with each entry and its game ID as soon as it is inserted.
+    pub fn add_entries<'a, I, F>(
+        &mut self,
+        entries: I,
+        platform: &PlatformId,
+        dat_id: i64,
+        mut on_entry: F,
+    ) -> Result<Vec<i64>>
+    where
+        I: IntoIterator<Item = &'a GameEntry>,
+        F: FnMut(&'a GameEntry, i64),
+    {
+        let mut game_ids = Vec::new();
+        let mut entries = entries.into_iter().peekable();
+        while entries.peek().is_some() {
+            let tx = self.memory_connection.transaction()?;
+            for entry in entries.by_ref().take(self.batch_size.unwrap_or(usize::MAX)) {
+                let game_id = insert_entry(entry, platform, dat_id, &tx)?;
+                on_entry(entry, game_id);
+                game_ids.push(game_id);
+            }
+            tx.commit()?;
+        }
+        Ok(game_ids)
+    }
+
+    /// Gets the ID, file name and SHA1 hash of every DAT in the database.
+    pub fn dats(&self) -> Result<Vec<(i64, String, String)>> {
+        let mut stmt = self
+            .memory_connection
+            .prepare("SELECT dat_id, file_name, sha1 FROM dat ORDER BY dat_id")?;
+        let dats = stmt.query_map(params![], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        Ok(dats.collect::<SqliteResult<_>>()?)
+    }
+
+    /// Removes a DAT and every entry that was added from it, returning the number of entries removed.
+    pub fn remove_dat(&mut self, dat_id: i64) -> Result<usize> {
+        let tx = self.memory_connection.transaction()?;
+        tx.execute(
+            "DELETE FROM clone_of
+            WHERE game_id IN (SELECT game_id FROM game WHERE dat_id = ?1)
+            OR parent_id IN (SELECT game_id FROM game WHERE dat_id = ?1)",
+            params![dat_id],
+        )?;
+        tx.execute(
+            "DELETE FROM serial WHERE game_id IN (SELECT game_id FROM game WHERE dat_id = ?1)",
+            params![dat_id],
+        )?;
+        tx.execute(
+            "DELETE FROM rom WHERE game_id IN (SELECT game_id FROM game WHERE dat_id = ?1)",
+            params![dat_id],
+        )?;
+        tx.execute(
+            "DELETE FROM language WHERE game_id IN (SELECT game_id FROM game WHERE dat_id = ?1)",
+            params![dat_id],
+        )?;
+        tx.execute(
+            "DELETE FROM publisher WHERE game_id IN (SELECT game_id FROM game WHERE dat_id = ?1)",
+            params![dat_id],
+        )?;
+        tx.execute(
+            "DELETE FROM dump_flag WHERE game_id IN (SELECT game_id FROM game WHERE dat_id = ?1)",
+            params![dat_id],
+        )?;
+        let removed = tx.execute("DELETE FROM game WHERE dat_id = ?1", params![dat_id])?;
+        tx.execute("DELETE FROM dat WHERE dat_id = ?1", params![dat_id])?;
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    pub fn add_clone_of(&mut self, game_id: i64, parent_id: i64) -> Result<()> {
+        self.memory_connection.execute_named(
+            "INSERT INTO clone_of (game_id, parent_id) VALUES (:game_id, :parent_id)",
+            named_params! {
+                ":game_id": game_id,
+                ":parent_id": parent_id,
+            },
+        )?;
         Ok(())
     }
 
     pub fn save<T: AsRef<Path>>(
-        mut self,
+        self,
         path: T,
         step_calback: Option<fn(_: Progress)>,
     ) -> Result<(String, String)> {
-        let res = write_meta_table(&mut self.memory_connection)?;
-        let path = path.as_ref();
-
-        if path.exists() {
+        if path.as_ref().exists() {
             return Err(DatabaseError::IOError(io::Error::new(
                 ErrorKind::AlreadyExists,
                 "The specified path already exists.",
             )));
         }
+        self.write(path, step_calback)
+    }
+
+    /// Saves the database over the database at the given path, with a new release UUID.
+    ///
+    /// The database is written to a temporary file next to the original first, which then replaces
+    /// the original, so that the original is left intact if saving fails.
+    pub fn overwrite<T: AsRef<Path>>(
+        self,
+        path: T,
+        step_calback: Option<fn(_: Progress)>,
+    ) -> Result<(String, String)> {
+        let path = path.as_ref();
+        let file_name = path.file_name().ok_or_else(|| {
+            io::Error::new(ErrorKind::InvalidInput, "The specified path is not a file.")
+        })?;
+        let mut temp_name = file_name.to_os_string();
+        temp_name.push(".tmp");
+        let temp_path = path.with_file_name(temp_name);
+        if temp_path.exists() {
+            std::fs::remove_file(&temp_path)?;
+        }
+        let res = match self.write(&temp_path, step_calback) {
+            Ok(res) => res,
+            Err(err) => {
+                let _ = std::fs::remove_file(&temp_path);
+                return Err(err);
+            }
+        };
+        std::fs::rename(&temp_path, path)?;
+        Ok(res)
+    }
+
+    fn write<T: AsRef<Path>>(
+        mut self,
+        path: T,
+        step_calback: Option<fn(_: Progress)>,
+    ) -> Result<(String, String)> {
+        create_indexes(&mut self.memory_connection)?;
+        let res = write_meta_table(&mut self.memory_connection, self.selection.as_deref())?;
         let mut target = Connection::open(path)?;
         let backup = Backup::new(&self.memory_connection, &mut target)?;
         backup.run_to_completion(5, Duration::new(0, 0), step_calback)?;
@@ -97,7 +295,7 @@ fn get_unix_time_string() -> String {
         .to_string()
 }
 
-fn write_meta_table(conn: &mut Connection) -> SqliteResult<(String, String)> {
+fn write_meta_table(conn: &mut Connection, selection: Option<&str>) -> SqliteResult<(String, String)> {
     let tx = conn.transaction()?;
     tx.execute(
         "CREATE TABLE shiragame (
@@ -106,28 +304,101 @@ fn write_meta_table(conn: &mut Connection) -> SqliteResult<(String, String)> {
         stone_version TEXT,
         generated TEXT,
         release TEXT,
-        aggregator TEXT
+        aggregator TEXT,
+        selection TEXT
     )",
         params! {},
     )?;
     let uuid = Uuid::new_v4().to_string();
     let time = get_unix_time_string();
-    tx.execute_named("INSERT INTO shiragame (shiragame, schema_version, stone_version, generated, release, aggregator)
-                                        VALUES(:shiragame, :schema_version, :stone_version, :generated, :release, :aggregator)",
+    tx.execute_named("INSERT INTO shiragame (shiragame, schema_version, stone_version, generated, release, aggregator, selection)
+                                        VALUES(:shiragame, :schema_version, :stone_version, :generated, :release, :aggregator, :selection)",
                     named_params! {
                         ":shiragame": "shiragame",
                         ":schema_version": SCHEMA_VERSION,
                         ":stone_version": StonePlatforms::version(),
                         ":generated": time,
                         ":release": uuid,
-                        ":aggregator": "shiratsu"
+                        ":aggregator": "shiratsu",
+                        ":selection": selection
                     })?;
     tx.commit()?;
     Ok((uuid, time))
 }
 
+/// Creates the indexes and the full-text search table once every entry has been added,
+/// which is faster than maintaining them for every insert.
+fn create_indexes(conn: &mut Connection) -> SqliteResult<()> {
+    let tx = conn.transaction()?;
+    tx.execute_batch(
+        "CREATE INDEX game_platform_id_idx ON game (platform_id);
+        CREATE INDEX rom_sha256_idx ON rom (sha256);
+        CREATE INDEX rom_xxh3_idx ON rom (xxh3);
+        CREATE INDEX rom_sha1_idx ON rom (sha1);
+        CREATE INDEX rom_md5_idx ON rom (md5);
+        CREATE INDEX rom_crc_idx ON rom (crc);
+        CREATE INDEX rom_game_id_idx ON rom (game_id);
+        CREATE INDEX serial_normalized_idx ON serial (normalized);
+        CREATE INDEX serial_game_id_idx ON serial (game_id);
+        CREATE INDEX language_language_idx ON language (language);
+        CREATE INDEX language_game_id_idx ON language (game_id);
+        CREATE INDEX publisher_publisher_idx ON publisher (publisher);
+        CREATE INDEX publisher_game_id_idx ON publisher (game_id);
+        CREATE INDEX dump_flag_flag_idx ON dump_flag (flag);
+        CREATE INDEX dump_flag_game_id_idx ON dump_flag (game_id);
+
+        CREATE VIRTUAL TABLE game_title USING fts5 (
+            entry_title,
+            release_title,
+            content = 'game',
+            content_rowid = 'game_id'
+        );
+        INSERT INTO game_title (game_title) VALUES ('rebuild');",
+    )?;
+    tx.commit()
+}
+
+/// Drops everything `create_indexes` and `write_meta_table` create, so that they can be
+/// created again when an existing database is saved.
+fn drop_indexes(conn: &mut Connection) -> SqliteResult<()> {
+    let tx = conn.transaction()?;
+    tx.execute_batch(
+        "DROP INDEX IF EXISTS game_platform_id_idx;
+        DROP INDEX IF EXISTS rom_sha256_idx;
+        DROP INDEX IF EXISTS rom_xxh3_idx;
+        DROP INDEX IF EXISTS rom_sha1_idx;
+        DROP INDEX IF EXISTS rom_md5_idx;
+        DROP INDEX IF EXISTS rom_crc_idx;
+        DROP INDEX IF EXISTS rom_game_id_idx;
+        DROP INDEX IF EXISTS serial_normalized_idx;
+        DROP INDEX IF EXISTS serial_game_id_idx;
+        DROP INDEX IF EXISTS language_language_idx;
+        DROP INDEX IF EXISTS language_game_id_idx;
+        DROP INDEX IF EXISTS publisher_publisher_idx;
+        DROP INDEX IF EXISTS publisher_game_id_idx;
+        DROP INDEX IF EXISTS dump_flag_flag_idx;
+        DROP INDEX IF EXISTS dump_flag_game_id_idx;
+        DROP TABLE IF EXISTS game_title;
+        DROP TABLE IF EXISTS shiragame;",
+    )?;
+    tx.commit()
+}
+
 fn create_database(conn: &mut Connection) -> SqliteResult<()> {
     let tx = conn.transaction()?;
+    tx.execute(
+        "CREATE TABLE dat ( 
+        dat_id INTEGER PRIMARY KEY,
+        file_name TEXT NOT NULL,
+        name TEXT,
+        version TEXT,
+        date TEXT,
+        sha1 TEXT NOT NULL,
+        source TEXT NOT NULL
+    )",
+        params![],
+    )?;
+
     tx.execute(
         "CREATE TABLE game ( 
         game_id INTEGER PRIMARY KEY,
@@ -141,9 +412,13 @@ fn create_database(conn: &mut Connection) -> SqliteResult<()> {
         is_demo BOOLEAN NOT NULL,
         is_system BOOLEAN NOT NULL,
         version TEXT,
+        release_year INTEGER,
+        release_date TEXT,
         status TEXT,
         naming_convention TEXT,
-        source TEXT NOT NULL
+        source TEXT NOT NULL,
+        dat_id INTEGER NOT NULL,
+        FOREIGN KEY (dat_id) REFERENCES dat (dat_id)
     )",
         params![],
     )?;
@@ -165,28 +440,96 @@ fn create_database(conn: &mut Connection) -> SqliteResult<()> {
         md5 TEXT,
         crc TEXT,
         sha1 TEXT,
+        sha256 TEXT,
+        xxh3 TEXT,
         size INTEGER NOT NULL,
+        status TEXT NOT NULL,
+        game_id INTEGER NOT NULL,
+        FOREIGN KEY (game_id) REFERENCES game (game_id)
+    )",
+        params![],
+    )?;
+
+    tx.execute(
+        "CREATE TABLE language ( 
+        language TEXT NOT NULL,
+        game_id INTEGER NOT NULL,
+        FOREIGN KEY (game_id) REFERENCES game (game_id)
+    )",
+        params![],
+    )?;
+
+    tx.execute(
+        "CREATE TABLE publisher ( 
+        publisher TEXT NOT NULL,
+        game_id INTEGER NOT NULL,
+        FOREIGN KEY (game_id) REFERENCES game (game_id)
+    )",
+        params![],
+    )?;
+
+    tx.execute(
+        "CREATE TABLE dump_flag ( 
+        flag TEXT NOT NULL,
         game_id INTEGER NOT NULL,
         FOREIGN KEY (game_id) REFERENCES game (game_id)
     )",
         params![],
     )?;
+
+    tx.execute(
+        "CREATE TABLE clone_of ( 
+        game_id INTEGER NOT NULL,
+        parent_id INTEGER NOT NULL,
+        FOREIGN KEY (game_id) REFERENCES game (game_id),
+        FOREIGN KEY (parent_id) REFERENCES game (game_id)
+    )",
+        params![],
+    )?;
     tx.commit()
 }
 
+fn insert_dat(
+    file_name: &str,
+    header: Option<&DatHeader>,
+    sha1: &str,
+    source: &str,
+    conn: &mut Connection,
+) -> SqliteResult<i64> {
+    conn.execute_named(r#"
+        INSERT INTO dat (
+            file_name,
+            name,
+            version,
+            date,
+            sha1,
+            source
+        )
+        VALUES (:file_name, :name, :version, :date, :sha1, :source)
+    "#,
+    named_params! {
+        ":file_name": file_name,
+        ":name": header.and_then(|h| h.name()),
+        ":version": header.and_then(|h| h.version()),
+        ":date": header.and_then(|h| h.date()),
+        ":sha1": sha1,
+        ":source": source,
+    })?;
+    Ok(conn.last_insert_rowid())
+}
+
 fn insert_entry(
     entry: &GameEntry,
     platform: &PlatformId,
-    conn: &mut Connection,
-) -> SqliteResult<()> {
-    let tx = conn.transaction()?;
-
+    dat_id: i64,
+    conn: &Connection,
+) -> SqliteResult<i64> {
     let region_str = entry
         .info()
         .map(|n| n.region())
         .map(|r| Region::to_normalized_region_string(r));
 
-    tx.execute_named(r#"
+    conn.prepare_cached(r#"
         INSERT INTO game (
             platform_id,
             entry_name,
@@ -198,13 +541,15 @@ fn insert_entry(
             is_demo,
             is_system,
             version,
+            release_year,
+            release_date,
             status,
             naming_convention,
-            source
+            source,
+            dat_id
         )
-        VALUES (:platform_id, :entry_name, :entry_title, :release_title, :region, :part_number, :is_unlicensed, :is_demo, :is_system, :version, :status, :naming_convention, :source)
-    "#,
-    named_params! {
+        VALUES (:platform_id, :entry_name, :entry_title, :release_title, :region, :part_number, :is_unlicensed, :is_demo, :is_system, :version, :release_year, :release_date, :status, :naming_convention, :source, :dat_id)
+    "#)?.execute_named(named_params! {
         ":platform_id": platform.as_ref(),
         ":entry_name": entry.entry_name(),
         ":entry_title": entry.info().map(|n| n.entry_title()),
@@ -215,42 +560,51 @@ fn insert_entry(
         ":is_demo": entry.info().map(|n| n.is_demo()).unwrap_or(false),
         ":is_system": entry.info().map(|n| n.is_system()).unwrap_or(false),
         ":version": entry.info().map(|n| n.version()),
+        ":release_year": entry.info().and_then(|n| n.release_year()),
+        ":release_date": entry.info().and_then(|n| n.release_date()),
         ":status": entry.info().map(|n| n.development_status()).unwrap_or(DevelopmentStatus::Release).as_ref(),
         ":naming_convention": entry.info().map(|n| n.naming_convention()).unwrap_or(NamingConvention::Unknown).as_ref(),
-        ":source": entry.source()
+        ":source": entry.source(),
+        ":dat_id": dat_id,
     })?;
 
-    let game_id = tx.last_insert_rowid();
-    
-    for rom in entry.rom_entries().iter() {
-        tx.execute_named(
-            r#"
+    let game_id = conn.last_insert_rowid();
+
+    let mut insert_rom = conn.prepare_cached(
+        r#"
             INSERT INTO rom(
                 file_name,
                 mimetype,
                 md5,
                 crc,
                 sha1,
+                sha256,
+                xxh3,
                 size,
+                status,
                 game_id
             )
-            VALUES (:file_name, :mimetype, :md5, :crc, :sha1, :size, :game_id)
+            VALUES (:file_name, :mimetype, :md5, :crc, :sha1, :sha256, :xxh3, :size, :status, :game_id)
         "#,
-            named_params! {
-                ":file_name": rom.file_name(),
-                ":mimetype": find_mimetype(platform, rom.file_name(), rom.hash_md5()),
-                ":md5": rom.hash_md5(),
-                ":crc": rom.hash_crc(),
-                ":sha1": rom.hash_sha1(),
-                ":size": rom.size(),
-                ":game_id": game_id,
-            },
-        )?;
+    )?;
+    for rom in entry.rom_entries().iter() {
+        let md5 = rom.hash_md5().map(|h| h.to_string());
+        insert_rom.execute_named(named_params! {
+            ":file_name": rom.file_name(),
+            ":mimetype": find_mimetype(platform, rom.file_name(), md5.as_deref()),
+            ":md5": md5,
+            ":crc": rom.hash_crc().map(|h| h.to_string()),
+            ":sha1": rom.hash_sha1().map(|h| h.to_string()),
+            ":sha256": rom.hash_sha256().map(|h| h.to_string()),
+            ":xxh3": rom.hash_xxh3().map(|h| h.to_string()),
+            ":size": rom.size(),
+            ":status": rom.status().as_ref(),
+            ":game_id": game_id,
+        })?;
     }
 
-    for serial in entry.serials().iter() {
-        tx.execute_named(
-            r#"
+    let mut insert_serial = conn.prepare_cached(
+        r#"
             INSERT INTO serial(
                 serial,
                 normalized,
@@ -258,13 +612,178 @@ fn insert_entry(
             )
             VALUES (:serial, :normalized, :game_id)
         "#,
-            named_params! {
-                ":serial" : serial.as_ref(),
-                ":normalized" : serial.as_normalized(platform).as_ref().as_ref(),
-                ":game_id": game_id,
-            },
-        )?;
+    )?;
+    for serial in entry.serials().iter() {
+        insert_serial.execute_named(named_params! {
+            ":serial" : serial.as_ref(),
+            ":normalized" : serial.as_normalized(platform).as_ref().as_ref(),
+            ":game_id": game_id,
+        })?;
     }
 
-    tx.commit()
+    let mut insert_language = conn.prepare_cached(
+        r#"
+            INSERT INTO language(
+                language,
+                game_id
+            )
+            VALUES (:language, :game_id)
+        "#,
+    )?;
+    for language in entry.info().map(|n| n.languages()).unwrap_or_default().iter() {
+        insert_language.execute_named(named_params! {
+            ":language": language,
+            ":game_id": game_id,
+        })?;
+    }
+
+    let mut insert_publisher = conn.prepare_cached(
+        r#"
+            INSERT INTO publisher(
+                publisher,
+                game_id
+            )
+            VALUES (:publisher, :game_id)
+        "#,
+    )?;
+    for publisher in entry.info().map(|n| n.publishers()).unwrap_or_default().iter() {
+        insert_publisher.execute_named(named_params! {
+            ":publisher": publisher,
+            ":game_id": game_id,
+        })?;
+    }
+
+    let mut insert_dump_flag = conn.prepare_cached(
+        r#"
+            INSERT INTO dump_flag(
+                flag,
+                game_id
+            )
+            VALUES (:flag, :game_id)
+        "#,
+    )?;
+    for flag in entry.info().map(|n| n.dump_quality()).unwrap_or_default().iter() {
+        insert_dump_flag.execute_named(named_params! {
+            ":flag": flag.as_ref(),
+            ":game_id": game_id,
+        })?;
+    }
+
+    Ok(game_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("shiratsu-{}-{}.db", name, std::process::id()))
+    }
+
+    fn count(db: &ShiratsuDatabase, table: &str) -> i64 {
+        db.memory_connection
+            .query_row(&format!("SELECT COUNT(*) FROM {}", table), params![], |row| row.get(0))
+            .unwrap()
+    }
+
+    fn add_dat(db: &mut ShiratsuDatabase, file_name: &str, platform: &PlatformId, dat: &str) -> i64 {
+        use shiratsu_dat::nointro::FromNoIntro;
+        let entries: Vec<GameEntry> = GameEntry::try_from_nointro_str(dat)
+            .unwrap()
+            .into_iter()
+            .collect::<result::Result<_, _>>()
+            .unwrap();
+        let dat_id = db.add_dat(file_name, None, file_name, "No-Intro").unwrap();
+        let mut inserted = Vec::new();
+        let ids = db
+            .add_entries(&entries, platform, dat_id, |entry, id| inserted.push((entry.entry_name().to_string(), id)))
+            .unwrap();
+        assert_eq!(entries.iter().map(|e| e.entry_name().to_string()).zip(ids.iter().copied()).collect::<Vec<_>>(), inserted);
+        for (entry, id) in entries.iter().zip(ids.iter()) {
+            if let Some(parent) = entry.parent_name() {
+                let parent = entries.iter().position(|e| e.entry_name() == parent).unwrap();
+                db.add_clone_of(*id, ids[parent]).unwrap();
+            }
+        }
+        dat_id
+    }
+
+    #[test]
+    fn removes_dats() {
+        use std::convert::TryInto;
+        let gb: &PlatformId = "NINTENDO_GB".try_into().unwrap();
+        let nes: &PlatformId = "NINTENDO_NES".try_into().unwrap();
+        let path = temp_path("remove");
+        let mut db = ShiratsuDatabase::new().unwrap();
+        add_dat(
+            &mut db,
+            "gb.dat",
+            gb,
+            r#"<datafile><header><homepage>No-Intro</homepage></header>
+            <game name="Tetris (World)"><description>Tetris (World)</description>
+                <rom name="Tetris (World).gb" size="32768" crc="46DF91AD" serial="DMG-TRA"/></game>
+            <game name="Tetris (Japan) (En)" cloneof="Tetris (World)"><description>Tetris (Japan) (En)</description>
+                <rom name="Tetris (Japan) (En).gb" size="32768" crc="46DF91AE"/></game>
+            </datafile>"#,
+        );
+        add_dat(
+            &mut db,
+            "nes.dat",
+            nes,
+            r#"<datafile><header><homepage>No-Intro</homepage></header>
+            <game name="Mother (Japan)"><description>Mother (Japan)</description>
+                <rom name="Mother (Japan).nes" size="262160" crc="42AC9F0D" serial="HVC-MX"/></game>
+            </datafile>"#,
+        );
+        db.save(&path, None).unwrap();
+
+        let mut db = ShiratsuDatabase::open(&path).unwrap();
+        let dats = db.dats().unwrap();
+        assert_eq!(
+            vec!["gb.dat", "nes.dat"],
+            dats.iter().map(|(_, file_name, _)| file_name.as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!("gb.dat", dats[0].2);
+        assert_eq!(2, count(&db, "serial"));
+        assert_eq!(1, count(&db, "clone_of"));
+        assert_eq!(2, db.remove_dat(dats[0].0).unwrap());
+
+        assert_eq!(1, db.dats().unwrap().len());
+        assert_eq!(1, count(&db, "game"));
+        for table in &["rom", "serial", "language", "publisher", "dump_flag", "clone_of"] {
+            let orphans: i64 = db
+                .memory_connection
+                .query_row(
+                    &format!("SELECT COUNT(*) FROM {} WHERE game_id NOT IN (SELECT game_id FROM game)", table),
+                    params![],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            assert_eq!(0, orphans, "orphaned rows in {}", table);
+        }
+        assert_eq!(1, count(&db, "rom"));
+        assert_eq!(1, count(&db, "serial"));
+        assert_eq!(0, count(&db, "clone_of"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn keeps_selection_rules() {
+        let path = temp_path("selection");
+        ShiratsuDatabase::new()
+            .unwrap()
+            .with_selection("regions: [USA]")
+            .save(&path, None)
+            .unwrap();
+        let db = ShiratsuDatabase::open(&path).unwrap();
+        assert_eq!(Some("regions: [USA]"), db.selection());
+        db.overwrite(&path, None).unwrap();
+        assert_eq!(Some("regions: [USA]"), ShiratsuDatabase::open(&path).unwrap().selection());
+        assert!(!path.with_extension("db.tmp").exists());
+        std::fs::remove_file(&path).unwrap();
+
+        ShiratsuDatabase::new().unwrap().save(&path, None).unwrap();
+        assert_eq!(None, ShiratsuDatabase::open(&path).unwrap().selection());
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -0,0 +1,432 @@
+use rusqlite::{params, Connection, OpenFlags};
+use serde::Serialize;
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+use std::path::Path;
+
+use anyhow::Result;
+
+/// A ROM of a game entry, as it is compared between releases.
+#[derive(Debug, Clone, Serialize)]
+pub struct RomInfo {
+    pub file_name: String,
+    pub size: i64,
+    pub crc: Option<String>,
+    pub md5: Option<String>,
+    pub sha1: Option<String>,
+    pub sha256: Option<String>,
+    pub xxh3: Option<String>,
+}
+
+/// The kinds of hash a ROM can be recognized by, strongest first.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+enum HashKind {
+    Sha256,
+    Sha1,
+    Md5,
+    Xxh3,
+    Crc,
+}
+
+const HASH_KINDS: [HashKind; 5] = [HashKind::Sha256, HashKind::Sha1, HashKind::Md5, HashKind::Xxh3, HashKind::Crc];
+
+/// Whether two hashes of the same kind agree, which they do unless both are known and differ,
+/// since older releases do not have every kind of hash.
+fn hashes_agree(old: &Option<String>, new: &Option<String>) -> bool {
+    match (old, new) {
+        (Some(old), Some(new)) => old == new,
+        _ => true,
+    }
+}
+
+impl RomInfo {
+    fn hash(&self, kind: HashKind) -> Option<String> {
+        match kind {
+            HashKind::Sha256 => self.sha256.clone(),
+            HashKind::Sha1 => self.sha1.clone(),
+            HashKind::Md5 => self.md5.clone(),
+            HashKind::Xxh3 => self.xxh3.clone(),
+            HashKind::Crc => self.crc.as_ref().map(|crc| format!("{}:{}", crc, self.size)),
+        }
+    }
+
+    /// The strongest hash of the ROM out of the given kinds, used to recognize the same ROM under a different name.
+    fn hash_key(&self, kinds: &[HashKind]) -> Option<(HashKind, String)> {
+        kinds.iter().find_map(|&kind| self.hash(kind).map(|hash| (kind, hash)))
+    }
+
+    /// Whether the ROM is unchanged, comparing only the hashes both releases have.
+    fn is_unchanged(&self, new: &RomInfo) -> bool {
+        self.file_name == new.file_name
+            && self.size == new.size
+            && hashes_agree(&self.crc, &new.crc)
+            && hashes_agree(&self.md5, &new.md5)
+            && hashes_agree(&self.sha1, &new.sha1)
+            && hashes_agree(&self.sha256, &new.sha256)
+            && hashes_agree(&self.xxh3, &new.xxh3)
+    }
+}
+
+/// The game entries of a release, keyed by platform ID and entry name,
+/// since game IDs are not stable between releases.
+#[derive(Debug, Default)]
+pub struct Release {
+    pub release: Option<String>,
+    pub games: BTreeMap<(String, String), Vec<RomInfo>>,
+}
+
+impl Release {
+    pub fn from_database<T: AsRef<Path>>(path: T) -> Result<Release> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let release = conn
+            .query_row("SELECT release FROM shiragame", params![], |row| row.get(0))
+            .ok();
+
+        let mut release = Release {
+            release,
+            games: BTreeMap::new(),
+        };
+        // Releases before schema 3.8 have no sha256 column, and releases before 3.9 no xxh3 column.
+        let column = |name: &'static str| {
+            if conn.prepare(&format!("SELECT {} FROM rom LIMIT 0", name)).is_ok() {
+                format!("rom.{}", name)
+            } else {
+                "NULL".to_string()
+            }
+        };
+        let mut stmt = conn.prepare(&format!(
+            "SELECT game.platform_id, game.entry_name, rom.file_name, rom.size, rom.crc, rom.md5, rom.sha1, {}, {}
+            FROM game LEFT JOIN rom ON rom.game_id = game.game_id",
+            column("sha256"),
+            column("xxh3")
+        ))?;
+        let mut rows = stmt.query(params![])?;
+        while let Some(row) = rows.next()? {
+            let roms = release
+                .games
+                .entry((row.get(0)?, row.get(1)?))
+                .or_default();
+            if let Some(file_name) = row.get::<_, Option<String>>(2)? {
+                roms.push(RomInfo {
+                    file_name,
+                    size: row.get(3)?,
+                    crc: row.get(4)?,
+                    md5: row.get(5)?,
+                    sha1: row.get(6)?,
+                    sha256: row.get(7)?,
+                    xxh3: row.get(8)?,
+                });
+            }
+        }
+        for roms in release.games.values_mut() {
+            roms.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+        }
+        Ok(release)
+    }
+
+    /// Whether any ROM in the release has a hash of the given kind.
+    fn has_hash(&self, kind: HashKind) -> bool {
+        self.games.values().flatten().any(|rom| rom.hash(kind).is_some())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct GameRef {
+    pub platform_id: String,
+    pub entry_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RomChange {
+    pub old: RomInfo,
+    pub new: RomInfo,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangedGame {
+    pub platform_id: String,
+    pub entry_name: String,
+    pub added_roms: Vec<RomInfo>,
+    pub removed_roms: Vec<RomInfo>,
+    pub changed_roms: Vec<RomChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RenamedGame {
+    pub platform_id: String,
+    pub old_entry_name: String,
+    pub new_entry_name: String,
+}
+
+/// The differences between two releases of a shiragame database.
+#[derive(Debug, Serialize)]
+pub struct Diff {
+    pub old_release: Option<String>,
+    pub new_release: Option<String>,
+    pub added: Vec<GameRef>,
+    pub removed: Vec<GameRef>,
+    pub changed: Vec<ChangedGame>,
+    pub renamed: Vec<RenamedGame>,
+}
+
+impl Diff {
+    /// Writes the diff as a human-readable changelog.
+    pub fn write_text<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(
+            w,
+            "shiragame {} to {}",
+            self.old_release.as_deref().unwrap_or("unknown"),
+            self.new_release.as_deref().unwrap_or("unknown")
+        )?;
+        writeln!(
+            w,
+            "{} added, {} removed, {} changed, {} renamed",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len(),
+            self.renamed.len()
+        )?;
+        for game in self.added.iter() {
+            writeln!(w, "[{}] + {}", game.platform_id, game.entry_name)?;
+        }
+        for game in self.removed.iter() {
+            writeln!(w, "[{}] - {}", game.platform_id, game.entry_name)?;
+        }
+        for game in self.renamed.iter() {
+            writeln!(
+                w,
+                "[{}] > {} renamed to {}",
+                game.platform_id, game.old_entry_name, game.new_entry_name
+            )?;
+        }
+        for game in self.changed.iter() {
+            writeln!(w, "[{}] ~ {}", game.platform_id, game.entry_name)?;
+            for rom in game.added_roms.iter() {
+                writeln!(w, "    + {}", rom.file_name)?;
+            }
+            for rom in game.removed_roms.iter() {
+                writeln!(w, "    - {}", rom.file_name)?;
+            }
+            for change in game.changed_roms.iter() {
+                write!(w, "    ~ {}", change.new.file_name)?;
+                for (name, old, new) in [
+                    ("size", Some(change.old.size.to_string()), Some(change.new.size.to_string())),
+                    ("crc", change.old.crc.clone(), change.new.crc.clone()),
+                    ("md5", change.old.md5.clone(), change.new.md5.clone()),
+                    ("sha1", change.old.sha1.clone(), change.new.sha1.clone()),
+                    ("sha256", change.old.sha256.clone(), change.new.sha256.clone()),
+                    ("xxh3", change.old.xxh3.clone(), change.new.xxh3.clone()),
+                ]
+                .iter()
+                {
+                    if old.is_some() && new.is_some() && old != new {
+                        write!(
+                            w,
+                            " ({} {} to {})",
+                            name,
+                            old.as_deref().unwrap_or("none"),
+                            new.as_deref().unwrap_or("none")
+                        )?;
+                    }
+                }
+                writeln!(w)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compares two releases.
+///
+/// A removed entry and an added entry of the same platform with identical ROM hashes
+/// are reported as a renamed entry instead.
+pub fn diff(old: &Release, new: &Release) -> Diff {
+    let mut removed = Vec::new();
+    let mut changed = Vec::new();
+    for ((platform_id, entry_name), old_roms) in old.games.iter() {
+        match new.games.get(&(platform_id.clone(), entry_name.clone())) {
+            None => removed.push((platform_id, entry_name, old_roms)),
+            Some(new_roms)
+                if new_roms.len() != old_roms.len()
+                    || old_roms.iter().zip(new_roms).any(|(old, new)| !old.is_unchanged(new)) =>
+            {
+                changed.push(diff_roms(platform_id, entry_name, old_roms, new_roms))
+            }
+            Some(_) => {}
+        }
+    }
+    let added = new
+        .games
+        .iter()
+        .filter(|(key, _)| !old.games.contains_key(*key))
+        .map(|((platform_id, entry_name), roms)| (platform_id, entry_name, roms))
+        .collect::<Vec<_>>();
+
+    // Match removed and added entries by platform and the set of ROM hashes,
+    // using only the kinds of hash both releases have.
+    let kinds = HASH_KINDS
+        .iter()
+        .copied()
+        .filter(|&kind| old.has_hash(kind) && new.has_hash(kind))
+        .collect::<Vec<_>>();
+    let hash_key = |platform_id: &str, roms: &[RomInfo]| -> Option<(String, Vec<(HashKind, String)>)> {
+        let mut hashes = roms.iter().map(|r| r.hash_key(&kinds)).collect::<Option<Vec<_>>>()?;
+        if hashes.is_empty() {
+            return None;
+        }
+        hashes.sort();
+        Some((platform_id.to_string(), hashes))
+    };
+    let mut added_by_hash: HashMap<_, Vec<usize>> = HashMap::new();
+    for (i, (platform_id, _, roms)) in added.iter().enumerate() {
+        if let Some(key) = hash_key(platform_id, roms) {
+            added_by_hash.entry(key).or_default().push(i);
+        }
+    }
+    let mut renamed = Vec::new();
+    let mut renamed_to = vec![false; added.len()];
+    let mut still_removed = Vec::new();
+    for (platform_id, entry_name, roms) in removed {
+        let to = hash_key(platform_id, roms)
+            .and_then(|key| added_by_hash.get_mut(&key))
+            .filter(|candidates| !candidates.is_empty())
+            .map(|candidates| candidates.remove(0));
+        match to {
+            Some(i) => {
+                renamed_to[i] = true;
+                renamed.push(RenamedGame {
+                    platform_id: platform_id.clone(),
+                    old_entry_name: entry_name.clone(),
+                    new_entry_name: added[i].1.clone(),
+                });
+            }
+            None => still_removed.push(GameRef {
+                platform_id: platform_id.clone(),
+                entry_name: entry_name.clone(),
+            }),
+        }
+    }
+
+    Diff {
+        old_release: old.release.clone(),
+        new_release: new.release.clone(),
+        added: added
+            .iter()
+            .zip(renamed_to)
+            .filter(|(_, renamed)| !renamed)
+            .map(|((platform_id, entry_name, _), _)| GameRef {
+                platform_id: platform_id.to_string(),
+                entry_name: entry_name.to_string(),
+            })
+            .collect(),
+        removed: still_removed,
+        changed,
+        renamed,
+    }
+}
+
+fn diff_roms(platform_id: &str, entry_name: &str, old: &[RomInfo], new: &[RomInfo]) -> ChangedGame {
+    let old_by_name = old.iter().map(|r| (&r.file_name, r)).collect::<HashMap<_, _>>();
+    let new_by_name = new.iter().map(|r| (&r.file_name, r)).collect::<HashMap<_, _>>();
+    ChangedGame {
+        platform_id: platform_id.to_string(),
+        entry_name: entry_name.to_string(),
+        added_roms: new
+            .iter()
+            .filter(|r| !old_by_name.contains_key(&r.file_name))
+            .cloned()
+            .collect(),
+        removed_roms: old
+            .iter()
+            .filter(|r| !new_by_name.contains_key(&r.file_name))
+            .cloned()
+            .collect(),
+        changed_roms: old
+            .iter()
+            .filter_map(|o| match new_by_name.get(&o.file_name) {
+                Some(n) if !o.is_unchanged(n) => Some(RomChange {
+                    old: o.clone(),
+                    new: (*n).clone(),
+                }),
+                _ => None,
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rom(file_name: &str, sha1: &str) -> RomInfo {
+        RomInfo {
+            file_name: file_name.to_string(),
+            size: 32768,
+            crc: None,
+            md5: None,
+            sha1: Some(sha1.to_string()),
+            sha256: None,
+            xxh3: None,
+        }
+    }
+
+    fn release(games: &[(&str, Vec<RomInfo>)]) -> Release {
+        Release {
+            release: None,
+            games: games
+                .iter()
+                .map(|(name, roms)| (("NINTENDO_GB".to_string(), name.to_string()), roms.clone()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn diffs_releases() {
+        let old = release(&[
+            ("Tetris (World)", vec![rom("Tetris (World).gb", "aa")]),
+            ("Dr. Mario (World)", vec![rom("Dr. Mario (World).gb", "bb")]),
+            ("Pokemon (USA)", vec![rom("Pokemon (USA).gb", "cc")]),
+        ]);
+        let new = release(&[
+            ("Tetris (World) (Rev 1)", vec![rom("Tetris (World) (Rev 1).gb", "aa")]),
+            ("Dr. Mario (World)", vec![rom("Dr. Mario (World).gb", "dd")]),
+            ("Kirby (USA)", vec![rom("Kirby (USA).gb", "ee")]),
+        ]);
+
+        let diff = diff(&old, &new);
+        assert_eq!("Kirby (USA)", diff.added[0].entry_name);
+        assert_eq!("Pokemon (USA)", diff.removed[0].entry_name);
+        assert_eq!("Tetris (World)", diff.renamed[0].old_entry_name);
+        assert_eq!("Tetris (World) (Rev 1)", diff.renamed[0].new_entry_name);
+        assert_eq!(1, diff.changed.len());
+        assert_eq!(Some("dd"), diff.changed[0].changed_roms[0].new.sha1.as_deref());
+        assert_eq!((1, 1, 1), (diff.added.len(), diff.removed.len(), diff.renamed.len()));
+
+        let mut text = Vec::new();
+        diff.write_text(&mut text).unwrap();
+        assert!(String::from_utf8(text).unwrap().contains("~ Dr. Mario (World).gb (sha1 bb to dd)"));
+    }
+
+    #[test]
+    fn compares_hashes_both_releases_have() {
+        let old = release(&[
+            ("Tetris (World)", vec![rom("Tetris (World).gb", "aa")]),
+            ("Dr. Mario (World)", vec![rom("Dr. Mario (World).gb", "bb")]),
+        ]);
+        let with_newer_hashes = |name: &str, sha1: &str| RomInfo {
+            sha256: Some(format!("{}256", sha1)),
+            xxh3: Some(format!("{}64", sha1)),
+            ..rom(name, sha1)
+        };
+        let new = release(&[
+            ("Tetris (World) (Rev 1)", vec![with_newer_hashes("Tetris (World) (Rev 1).gb", "aa")]),
+            ("Dr. Mario (World)", vec![with_newer_hashes("Dr. Mario (World).gb", "bb")]),
+        ]);
+
+        let diff = diff(&old, &new);
+        assert!(diff.changed.is_empty());
+        assert_eq!((0, 0, 1), (diff.added.len(), diff.removed.len(), diff.renamed.len()));
+        assert_eq!("Tetris (World) (Rev 1)", diff.renamed[0].new_entry_name);
+    }
+}
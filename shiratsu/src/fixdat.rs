@@ -0,0 +1,149 @@
+use crate::audit::{KnownRom, RomIndex};
+use shiratsu_dat::{GameEntry, RomEntry, RomStatus};
+
+/// The ROMs of the game entry that can be audited, leaving out ROMs that have never been dumped.
+fn dumped_roms(entry: &GameEntry) -> impl Iterator<Item = &RomEntry> {
+    entry.rom_entries().iter().filter(|rom| rom.status() != RomStatus::NoDump)
+}
+
+/// Indexes every dumped ROM of the game entries, in order, so that an audit against the index
+/// can be mapped back to the game entries with `missing_roms`.
+///
+/// A DAT given on the command line is not tied to a platform, so the ROMs are indexed under
+/// `group`, such as the name of the DAT, in place of a platform ID.
+///
+/// Returns the number of ROMs indexed, which is where the matches of the next indexed entries begin.
+pub fn index_entries<'a, I: IntoIterator<Item = &'a GameEntry>>(index: &mut RomIndex, group: &str, entries: I) -> usize {
+    let mut count = 0;
+    for entry in entries {
+        for rom in dumped_roms(entry) {
+            count += 1;
+            index.push(KnownRom {
+                platform_id: group.to_string(),
+                entry_name: entry.entry_name().to_string(),
+                file_name: rom.file_name().to_string(),
+                size: rom.size(),
+                crc: rom.hash_crc().map(|h| h.to_string()),
+                md5: rom.hash_md5().map(|h| h.to_string()),
+                sha1: rom.hash_sha1().map(|h| h.to_string()),
+                sha256: rom.hash_sha256().map(|h| h.to_string()),
+                xxh3: rom.hash_xxh3().map(|h| h.to_string()),
+            });
+        }
+    }
+    count
+}
+
+/// Collects the ROMs of each game entry that have no matching local file.
+///
+/// `matches` are the audit matches starting at the first ROM of the entries, as indexed
+/// by `index_entries`. Game entries with every ROM present are left out.
+// `Option::is_none_or` needs a newer Rust than the crate otherwise does.
+#[allow(clippy::unnecessary_map_or)]
+pub fn missing_roms<'a>(entries: &[&'a GameEntry], matches: &[Vec<usize>]) -> Vec<(&'a GameEntry, Vec<&'a RomEntry>)> {
+    let mut matches = matches.iter();
+    let mut missing = Vec::new();
+    for &entry in entries {
+        let roms: Vec<&RomEntry> = dumped_roms(entry)
+            .filter(|_| matches.next().map_or(true, |m| m.is_empty()))
+            .collect();
+        if !roms.is_empty() {
+            missing.push((entry, roms));
+        }
+    }
+    missing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{audit, hash_reader, GameStatus, LocalFile};
+    use shiratsu_dat::nointro::FromNoIntro;
+    use std::path::PathBuf;
+
+    #[test]
+    fn collects_missing_roms() {
+        let tetris = hash_reader(&b"tetris"[..]).unwrap();
+        let dat = format!(
+            r#"<datafile><header><homepage>No-Intro</homepage></header>
+            <game name="Tetris (World)"><description>Tetris (World)</description><rom name="Tetris (World).gb" size="{}" crc="{}" sha1="{}"/></game>
+            <game name="Pokemon (USA)"><description>Pokemon (USA)</description>
+                <rom name="Pokemon (USA) (Track 1).bin" size="{0}" crc="{1}" sha1="{2}"/>
+                <rom name="Pokemon (USA) (Track 2).bin" size="9" crc="00000000" sha1="0000000000000000000000000000000000000000"/>
+            </game>
+            <game name="Tetris (World) (Beta)"><description>Tetris (World) (Beta)</description>
+                <rom name="Tetris (World) (Beta).gb" size="{0}" crc="{1}" sha1="{2}"/>
+                <rom name="Tetris (World) (Beta).sav" size="8192" status="nodump"/>
+            </game></datafile>"#,
+            tetris.size, tetris.crc, tetris.sha1
+        );
+        let dat = GameEntry::try_from_nointro_str(&dat).unwrap();
+        let entries: Vec<&GameEntry> = dat.entries().iter().filter_map(|e| e.as_ref().ok()).collect();
+
+        let mut index = RomIndex::default();
+        index_entries(&mut index, "NINTENDO_GB", entries.iter().copied());
+        let audit = audit(
+            &index,
+            vec![LocalFile {
+                path: PathBuf::from("tetris.gb"),
+                inner: None,
+                hashes: tetris,
+            }],
+        );
+
+        let missing = missing_roms(&entries, &audit.matches);
+        assert_eq!(1, missing.len());
+        assert_eq!(4, index.roms().len());
+        assert_eq!("Pokemon (USA)", missing[0].0.entry_name());
+        assert_eq!("Pokemon (USA) (Track 2).bin", missing[0].1[0].file_name());
+        assert_eq!(1, missing[0].1.len());
+
+        let platforms = audit.by_platform(&index);
+        assert_eq!(GameStatus::Have, platforms["NINTENDO_GB"].games[2].status());
+    }
+
+    #[test]
+    fn collects_missing_roms_across_dats() {
+        let tetris = hash_reader(&b"tetris"[..]).unwrap();
+        let pokemon = hash_reader(&b"pokemon"[..]).unwrap();
+        let game = |name: &str, hashes: &crate::audit::FileHashes, extra: &str| {
+            format!(
+                r#"<game name="{0}"><description>{0}</description><rom name="{0}.gb" size="{1}" crc="{2}" sha1="{3}"/>{4}</game>"#,
+                name, hashes.size, hashes.crc, hashes.sha1, extra
+            )
+        };
+        let first = format!(
+            r#"<datafile><header><homepage>No-Intro</homepage></header>{}</datafile>"#,
+            game("Tetris (World)", &tetris, r#"<rom name="Tetris (World).sav" size="8192" status="nodump"/>"#)
+        );
+        let second = format!(
+            r#"<datafile><header><homepage>No-Intro</homepage></header>{}{}</datafile>"#,
+            game("Pokemon (USA)", &pokemon, ""),
+            game("Tetris (Japan)", &tetris, "")
+        );
+        let first = GameEntry::try_from_nointro_str(&first).unwrap();
+        let second = GameEntry::try_from_nointro_str(&second).unwrap();
+        let first: Vec<&GameEntry> = first.entries().iter().filter_map(|e| e.as_ref().ok()).collect();
+        let second: Vec<&GameEntry> = second.entries().iter().filter_map(|e| e.as_ref().ok()).collect();
+
+        let mut index = RomIndex::default();
+        let offset = index_entries(&mut index, "First", first.iter().copied());
+        index_entries(&mut index, "Second", second.iter().copied());
+        assert_eq!(1, offset);
+        let audit = audit(
+            &index,
+            vec![LocalFile {
+                path: PathBuf::from("pokemon.gb"),
+                inner: None,
+                hashes: pokemon,
+            }],
+        );
+
+        let missing = missing_roms(&first, &audit.matches[..offset]);
+        assert_eq!(1, missing.len());
+        assert_eq!("Tetris (World)", missing[0].0.entry_name());
+        let missing = missing_roms(&second, &audit.matches[offset..]);
+        assert_eq!(1, missing.len());
+        assert_eq!("Tetris (Japan)", missing[0].0.entry_name());
+    }
+}
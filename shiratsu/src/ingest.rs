@@ -1,8 +1,46 @@
+use sha1::Sha1;
 use shiratsu_stone::PlatformId;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryInto;
-use std::path::Path;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread;
 use walkdir::{DirEntry, WalkDir};
 
+use crate::archive;
+
+/// A DAT file to ingest, which may be a file inside a zip or 7z archive.
+#[derive(Debug, Clone)]
+pub struct DatFile {
+    /// The path of the DAT file, or of the archive the DAT file is in.
+    pub path: PathBuf,
+    /// The name of the DAT file inside the archive, if any.
+    pub inner: Option<String>,
+}
+
+impl DatFile {
+    /// The file name of the DAT, without any directories inside the archive.
+    pub fn file_name(&self) -> &OsStr {
+        match &self.inner {
+            Some(inner) => Path::new(inner)
+                .file_name()
+                .unwrap_or_else(|| OsStr::new(inner)),
+            None => self.path.file_name().unwrap_or_else(|| self.path.as_os_str()),
+        }
+    }
+
+    /// The path of the DAT, with the name of the DAT appended if it is inside an archive.
+    pub fn display_path(&self) -> PathBuf {
+        match &self.inner {
+            Some(inner) => self.path.join(inner),
+            None => self.path.clone(),
+        }
+    }
+}
+
 fn is_platform_id(entry: &DirEntry) -> bool {
     entry
         .file_name()
@@ -11,20 +49,25 @@ fn is_platform_id(entry: &DirEntry) -> bool {
         .is_some()
 }
 
+fn is_dat_name(name: &str) -> bool {
+    name.ends_with(".dat")
+}
+
 fn is_dat_file(entry: &DirEntry) -> bool {
-    entry
-        .file_name()
-        .to_str()
-        .map(|s| s.ends_with(".dat"))
-        .unwrap_or(false)
+    entry.file_name().to_str().map(is_dat_name).unwrap_or(false) || archive::is_archive(entry.path())
 }
 
-pub fn get_paths<T: AsRef<Path>>(root_path: T) -> Vec<(&'static PlatformId, DirEntry)> {
+/// Finds every DAT file in the platform directories under the root, including DAT files inside
+/// zip and 7z archives, which are ingested as if they were in the platform directory.
+///
+/// DAT files inside archives are only listed here, and are not decompressed until they are read.
+pub fn get_paths<T: AsRef<Path>>(root_path: T) -> anyhow::Result<Vec<(&'static PlatformId, DatFile)>> {
     let root_path = root_path.as_ref();
     let mut result = Vec::new();
     for entry in WalkDir::new(root_path)
         .min_depth(1)
         .max_depth(1)
+        .sort_by(|a, b| a.file_name().cmp(b.file_name()))
         .into_iter()
         .filter_entry(|entry| is_platform_id(entry))
         .flat_map(|ent| ent)
@@ -36,12 +79,284 @@ pub fn get_paths<T: AsRef<Path>>(root_path: T) -> Vec<(&'static PlatformId, DirE
             .unwrap();
         for dat in WalkDir::new(entry.path())
             .min_depth(1)
+            .sort_by(|a, b| a.file_name().cmp(b.file_name()))
             .into_iter()
             .filter_entry(|entry| is_dat_file(entry))
             .flat_map(|ent| ent)
         {
-            result.push((platform_id, dat));
+            if !archive::is_archive(dat.path()) {
+                result.push((platform_id, DatFile {
+                    path: dat.into_path(),
+                    inner: None,
+                }));
+                continue;
+            }
+            let mut names = archive::file_names(dat.path(), is_dat_name)?;
+            names.sort();
+            for name in names {
+                result.push((platform_id, DatFile {
+                    path: dat.path().to_path_buf(),
+                    inner: Some(name),
+                }));
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// How far ahead of the next result to write workers may run.
+fn lookahead() -> usize {
+    rayon::current_num_threads() * 2
+}
+
+/// Tracks how many results were written, so that workers only start items within the lookahead.
+#[derive(Default)]
+struct Window {
+    state: Mutex<WindowState>,
+    moved: Condvar,
+}
+
+#[derive(Default)]
+struct WindowState {
+    written: usize,
+    cancelled: bool,
+}
+
+impl Window {
+    /// Blocks until the item is within `size` of the next result to write.
+    /// Returns false if writing failed and the item should not be started.
+    fn wait_for(&self, index: usize, size: usize) -> bool {
+        let mut state = self.state.lock().unwrap();
+        while !state.cancelled && index >= state.written + size {
+            state = self.moved.wait(state).unwrap();
+        }
+        !state.cancelled
+    }
+
+    fn advance(&self) {
+        self.state.lock().unwrap().written += 1;
+        self.moved.notify_all();
+    }
+
+    fn cancel(&self) {
+        self.state.lock().unwrap().cancelled = true;
+        self.moved.notify_all();
+    }
+}
+
+/// Runs `work` over every item on a pool of worker threads, and hands each result to `write`
+/// on the calling thread in the same order as the items, no matter which worker finishes first.
+///
+/// Workers start items in order and never run more than a few items ahead of `write`, so only
+/// a bounded number of results are held in memory while waiting to be written.
+/// If `write` fails, no further items are started and the error is returned.
+pub fn par_map_ordered<T, R, P, W>(items: Vec<T>, work: P, mut write: W) -> anyhow::Result<()>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+    P: Fn(T) -> R + Send + Sync + 'static,
+    W: FnMut(R) -> anyhow::Result<()>,
+{
+    let (tx, rx) = mpsc::channel();
+    let window = Arc::new(Window::default());
+    let workers = {
+        let window = Arc::clone(&window);
+        thread::spawn(move || {
+            let size = lookahead();
+            let items = Mutex::new(items.into_iter().enumerate());
+            let (items, window, work) = (&items, &*window, &work);
+            rayon::scope(|scope| {
+                for _ in 0..rayon::current_num_threads() {
+                    let tx = tx.clone();
+                    scope.spawn(move |_| loop {
+                        let next = items.lock().unwrap().next();
+                        let (i, item) = match next {
+                            Some(next) => next,
+                            None => break,
+                        };
+                        // The receiver is only gone if writing failed, so the result is not needed.
+                        if !window.wait_for(i, size) || tx.send((i, work(item))).is_err() {
+                            break;
+                        }
+                    });
+                }
+            });
+        })
+    };
+
+    let mut pending = BTreeMap::new();
+    let mut next = 0;
+    let mut result = Ok(());
+    'receive: for (i, value) in rx.iter() {
+        pending.insert(i, value);
+        while let Some(value) = pending.remove(&next) {
+            next += 1;
+            if let Err(err) = write(value) {
+                result = Err(err);
+                break 'receive;
+            }
+            window.advance();
         }
     }
+    window.cancel();
+    drop(rx);
+    workers
+        .join()
+        .map_err(|_| anyhow::anyhow!("A worker thread panicked."))?;
     result
 }
+
+/// Computes the SHA1 hash of every DAT as a lowercase hex string, in the same order as the DATs.
+///
+/// The DATs inside each archive are hashed in a single pass over the archive.
+pub fn hash_all(dats: &[(&'static PlatformId, DatFile)]) -> anyhow::Result<Vec<String>> {
+    let mut archived = HashMap::new();
+    for (_, dat) in dats.iter().filter(|(_, dat)| dat.inner.is_some()) {
+        if archived.contains_key(&dat.path) {
+            continue;
+        }
+        let mut hashes = HashMap::new();
+        archive::for_each_file(&dat.path, |name, reader| {
+            if is_dat_name(name) {
+                hashes.insert(name.to_string(), hash_reader(reader)?);
+            }
+            Ok(())
+        })?;
+        archived.insert(dat.path.clone(), hashes);
+    }
+    dats.iter()
+        .map(|(_, dat)| match &dat.inner {
+            Some(name) => archived[&dat.path]
+                .get(name)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("{} is not in {}.", name, dat.path.display())),
+            None => Ok(hash_file(&dat.path)?),
+        })
+        .collect()
+}
+
+/// Computes the SHA1 hash of the file at the given path as a lowercase hex string.
+pub fn hash_file<T: AsRef<Path>>(path: T) -> io::Result<String> {
+    hash_reader(File::open(path)?)
+}
+
+/// Computes the SHA1 hash of everything read from the reader as a lowercase hex string.
+fn hash_reader<R: Read>(reader: R) -> io::Result<String> {
+    read_hashed(reader, &mut io::sink())
+}
+
+/// Reads everything from the reader into memory, computing its SHA1 hash as a lowercase hex string
+/// in the same pass.
+pub fn read_to_end_hashed<R: Read>(reader: R) -> io::Result<(Vec<u8>, String)> {
+    let mut data = Vec::new();
+    let sha1 = read_hashed(reader, &mut data)?;
+    Ok((data, sha1))
+}
+
+fn read_hashed<R: Read, W: Write>(mut reader: R, writer: &mut W) -> io::Result<String> {
+    let mut hasher = Sha1::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        match reader.read(&mut buf)? {
+            0 => break,
+            n => {
+                hasher.update(&buf[..n]);
+                writer.write_all(&buf[..n])?;
+            }
+        }
+    }
+    Ok(hasher.digest().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn writes_in_order() {
+        let mut written = Vec::new();
+        par_map_ordered(
+            (0..32u64).collect(),
+            |i| {
+                thread::sleep(Duration::from_millis((32 - i) % 7));
+                i * 2
+            },
+            |value| {
+                written.push(value);
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!((0..32u64).map(|i| i * 2).collect::<Vec<_>>(), written);
+    }
+
+    #[test]
+    fn runs_within_lookahead() {
+        let written = Arc::new(AtomicUsize::new(0));
+        let started = Arc::clone(&written);
+        let count = (lookahead() * 4) as u64;
+        par_map_ordered(
+            (0..count).collect(),
+            move |i| {
+                assert!((i as usize) < started.load(Ordering::SeqCst) + lookahead());
+                thread::sleep(Duration::from_millis(if i % 5 == 0 { 10 } else { 1 }));
+                i
+            },
+            |_| {
+                written.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn stops_after_write_error() {
+        let worked = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&worked);
+        let count = lookahead() * 8;
+        let result = par_map_ordered(
+            (0..count).collect(),
+            move |i| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(1));
+                i
+            },
+            |_| Err(anyhow::anyhow!("write failed")),
+        );
+        assert!(result.is_err());
+        assert!(worked.load(Ordering::SeqCst) <= lookahead());
+    }
+
+    #[test]
+    fn hashes_archived_dats() {
+        use std::io::Write;
+        use zip::write::FileOptions;
+        use zip::ZipWriter;
+
+        let dir = std::env::temp_dir().join(format!("shiratsu-ingest-{}", std::process::id()));
+        let platform_dir = dir.join("NINTENDO_GB");
+        std::fs::create_dir_all(&platform_dir).unwrap();
+        std::fs::write(platform_dir.join("a.dat"), b"<datafile>a</datafile>").unwrap();
+        let mut zip = ZipWriter::new(File::create(platform_dir.join("pack.zip")).unwrap());
+        for (name, data) in [("c.dat", "<datafile>c</datafile>"), ("b.dat", "<datafile>b</datafile>")].iter() {
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(data.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let dats = get_paths(&dir).unwrap();
+        let hashes = hash_all(&dats).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let names = dats.iter().map(|(_, dat)| dat.file_name().to_str().unwrap()).collect::<Vec<_>>();
+        assert_eq!(vec!["a.dat", "b.dat", "c.dat"], names);
+        let expected = ["a", "b", "c"]
+            .iter()
+            .map(|c| hash_reader(format!("<datafile>{}</datafile>", c).as_bytes()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(expected, hashes);
+    }
+}
@@ -0,0 +1,3 @@
+//! The Shiragame database layer of shiratsu, shared by the command line tool and its benchmarks.
+
+pub mod database;
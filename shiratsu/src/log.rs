@@ -1,3 +1,5 @@
+use crate::audit::GameStatus;
+use crate::rename::SkipReason;
 use crate::Event;
 use colored::*;
 use console::style;
@@ -13,7 +15,7 @@ lazy_static! {
     static ref PB_STYLE: ProgressStyle = ProgressStyle::default_spinner()
                                             // .tick_strings(&["⠁ ","⠂ ", "⠄ ", "⡀ ", "⢀ ", "⠠ ", "⠐ ", "⠈ ", ""])
                                             .tick_strings(&["⠋", "⠙", "⠸", "⠴", "⠦", "⠇", &format!("{}", "✓".green())])
-                                            .template("{prefix:.bold.dim} {spinner} [{pos}/{len}] {wide_msg}");
+                                            .template("{prefix:.bold.dim} {spinner} [{pos}] {wide_msg}");
     static ref SAVE_PB_STYLE: ProgressStyle = ProgressStyle::default_spinner()
                                             // .tick_strings(&["⠁ ","⠂ ", "⠄ ", "⡀ ", "⢀ ", "⠠ ", "⠐ ", "⠈ ", ""])
                                             .tick_strings(&["⠋", "⠙", "⠸", "⠴", "⠦", "⠇", &format!("{}", "✓".green())])
@@ -74,25 +76,59 @@ pub fn print_event(e: Event) {
                 style(p.display()).cyan(),
             )
         }
-        Event::FoundDatFile(pb, p, len, platform_id, source, root, filelog) => {
+        Event::UpdatingDatabase(p, root) => {
+            info!(
+                root,
+                "Updating Shiragame database at {save_path}",
+                save_path = p.display()
+            );
+            println!(
+                "Updating Shiragame database at {}",
+                style(p.display()).cyan(),
+            )
+        }
+        Event::RemovedDat(file_name, count, root) => {
+            info!(
+                root,
+                "Removed {count} entries from changed or removed DAT {file_name}",
+                count = count,
+                file_name = file_name
+            );
+            println!(
+                "Removed {} entries from changed or removed DAT {}",
+                style(count).cyan(),
+                style(file_name).cyan(),
+            )
+        }
+        Event::UnchangedDats(count) => println!(
+            "Skipping {} unchanged DATs",
+            style(count).cyan(),
+        ),
+        Event::FoundDatFile(pb, p, platform_id, source, header, root, filelog) => {
+            let name = header.and_then(|h| h.name()).unwrap_or("Unknown");
+            let version = header.and_then(|h| h.version()).unwrap_or("Unknown");
             info!(
                 root,
                 "Found {} DAT File at {} ({})",
                 source = source,
                 path = p.display(),
-                platform_id = platform_id.as_ref()
+                platform_id = platform_id.as_ref();
+                "dat_name" => name,
+                "dat_version" => version,
             );
 
             info!(
                 filelog,
-                "{}: {}",
+                "{}: {} ({} version {})",
                 platform_id = platform_id.as_ref(),
                 path = p.display(),
+                name = name,
+                version = version,
             );
 
             pb.set_style(PB_STYLE.clone());
             pb.set_message(&format!("{}", p.display()));
-            pb.set_draw_delta(len / 100);
+            pb.set_draw_delta(100);
         }
         Event::ProcessEntry(pb, platform_id, p, entry_name, root) => {
             info!(
@@ -164,6 +200,11 @@ pub fn print_event(e: Event) {
                 platform_id.as_ref()
             );
         }
+        Event::MovedSortedArchive(p) => println!(
+            " {} Moved sorted archive to {}",
+            "✓".green(),
+            style(p.display()).cyan()
+        ),
         Event::SortingSuccess(count, now) => {
             println!(
                 " {} -- Sorted {} DATs in {} seconds",
@@ -172,6 +213,187 @@ pub fn print_event(e: Event) {
                 style(now).cyan(),
             );
         }
+        Event::LoadInternalSelectionRules => {
+            println!("Loading {}", style("internal 1G1R rules").cyan())
+        }
+        Event::LoadExternalSelectionRules => println!(
+            "Loading 1G1R rules from {}",
+            style("1g1r.yml").cyan()
+        ),
+        Event::LoadedSelectionRules(s) => {
+            println!(" {} Loaded 1G1R rules from {}", "✓".green(), s.cyan(),)
+        }
+        Event::SelectionSuccess(path, selected, total, now) => {
+            println!(
+                " {} -- Selected {} of {} entries into {} in {} seconds",
+                "✓ Success".green(),
+                style(selected).cyan(),
+                style(total).cyan(),
+                style(path.display()).cyan(),
+                style(now).cyan(),
+            );
+        }
+        Event::AuditingDirectory(dir, db) => println!(
+            "Auditing {} against {}",
+            style(dir.display()).cyan(),
+            style(db.display()).cyan(),
+        ),
+        Event::HashedFile(pb, file) => {
+            if pb.position() == 0 {
+                pb.set_style(PB_STYLE.clone());
+            }
+            pb.set_message(&format!("{}", file));
+            pb.inc(1);
+        }
+        Event::UnreadableFile(pb, path, err) => {
+            let message = format!(
+                " {} -- Skipping {}, which could not be read: {}",
+                "! Warning".yellow(),
+                style(path.display()).cyan(),
+                err
+            );
+            if pb.is_hidden() {
+                eprintln!("{}", message);
+            } else {
+                pb.println(message);
+            }
+        }
+        Event::HashingSuccess(pb, count) => {
+            pb.finish_with_message(&format!("Hashed {} files", count));
+        }
+        Event::AuditedPlatform(platform_id, platform) => println!(
+            " {} {}: {} have, {} partial, {} missing",
+            "✓".green(),
+            style(platform_id).cyan(),
+            style(platform.count(GameStatus::Have)).green(),
+            style(platform.count(GameStatus::Partial)).yellow(),
+            style(platform.count(GameStatus::Missing)).red(),
+        ),
+        Event::AuditSuccess(report, files, unknown, now) => println!(
+            " {} -- Audited {} files ({} unknown) in {} seconds, report written to {}",
+            "✓ Success".green(),
+            style(files).cyan(),
+            style(unknown).cyan(),
+            style(now).cyan(),
+            style(report.display()).cyan(),
+        ),
+        Event::WroteFixdat(path, missing, total) => println!(
+            " {} Missing {} of {} entries, fixdat written to {}",
+            "✓".green(),
+            style(missing).cyan(),
+            style(total).cyan(),
+            style(path.display()).cyan(),
+        ),
+        Event::FixdatSuccess(count, missing, now) => println!(
+            " {} -- Wrote {} fixdats with {} missing entries in {} seconds",
+            "✓ Success".green(),
+            style(count).cyan(),
+            style(missing).cyan(),
+            style(now).cyan(),
+        ),
+        Event::ValidatingDatabase(path) => println!(
+            "Validating {} against the shiragame specification",
+            style(path.display()).cyan(),
+        ),
+        Event::ViolatedSpecification(violation) => eprintln!(
+            " {} [{}] {}{}",
+            "✘".red(),
+            style(violation.rule).cyan(),
+            violation.message,
+            violation
+                .count
+                .map(|count| format!(" ({} rows)", count))
+                .unwrap_or_default(),
+        ),
+        Event::ValidationSuccess(report) => println!(
+            " {} -- The database conforms to the specification, report written to {}",
+            "✓ Success".green(),
+            style(report.display()).cyan(),
+        ),
+        Event::DiffingDatabases(old, new) => println!(
+            "Comparing {} to {}",
+            style(old.display()).cyan(),
+            style(new.display()).cyan(),
+        ),
+        Event::DiffSuccess(diff, text, json) => println!(
+            " {} -- {} added, {} removed, {} changed and {} renamed entries, changelog written to {} and {}",
+            "✓ Success".green(),
+            style(diff.added.len()).cyan(),
+            style(diff.removed.len()).cyan(),
+            style(diff.changed.len()).cyan(),
+            style(diff.renamed.len()).cyan(),
+            style(text.display()).cyan(),
+            style(json.display()).cyan(),
+        ),
+        Event::PlannedRename(op) => println!(
+            " {} Would {} {} to {}",
+            "-".cyan(),
+            op.mode.as_ref(),
+            style(op.from.display()).cyan(),
+            style(op.to.display()).cyan(),
+        ),
+        Event::Renamed(op) => println!(
+            " {} {} {} to {}",
+            "✓".green(),
+            op.mode.as_ref(),
+            style(op.from.display()).cyan(),
+            style(op.to.display()).cyan(),
+        ),
+        Event::SkippedRename(path, reason) => match reason {
+            SkipReason::InArchive => eprintln!(
+                " {} -- Skipping {} inside an archive",
+                "! Warning".yellow(),
+                style(path.display()).cyan(),
+            ),
+            SkipReason::AlreadyNamed => {}
+            SkipReason::Conflict(other) => eprintln!(
+                " {} -- Skipping {}, which matches the same ROM as {}",
+                "! Warning".yellow(),
+                style(path.display()).cyan(),
+                style(other.display()).cyan(),
+            ),
+            SkipReason::DestinationExists(to) => eprintln!(
+                " {} -- Skipping {}, {} already exists",
+                "! Warning".yellow(),
+                style(path.display()).cyan(),
+                style(to.display()).cyan(),
+            ),
+            SkipReason::UnsafeName(name) => eprintln!(
+                " {} -- Skipping {}, {} is not a safe file name",
+                "! Warning".yellow(),
+                style(path.display()).cyan(),
+                style(name).cyan(),
+            ),
+        },
+        Event::RenameSuccess(count, skipped, journal, now) => match journal {
+            Some(journal) => println!(
+                " {} -- Renamed {} files ({} skipped) in {} seconds, journal written to {}",
+                "✓ Success".green(),
+                style(count).cyan(),
+                style(skipped).cyan(),
+                style(now).cyan(),
+                style(journal.display()).cyan(),
+            ),
+            None => println!(
+                " {} -- Would rename {} files ({} skipped)",
+                "✓ Dry run".green(),
+                style(count).cyan(),
+                style(skipped).cyan(),
+            ),
+        },
+        Event::UndidRename(op) => println!(
+            " {} Undid {} of {} to {}",
+            "✓".green(),
+            op.mode.as_ref(),
+            style(op.from.display()).cyan(),
+            style(op.to.display()).cyan(),
+        ),
+        Event::UndoRenameSuccess(count, journal) => println!(
+            " {} -- Undid {} renames from {}",
+            "✓ Success".green(),
+            style(count).cyan(),
+            style(journal.display()).cyan(),
+        ),
         Event::NoEntriesFound(filename, root) => {
             warn!(root, "No entries found for DAT {:#?}", filename);
             eprintln!(
@@ -180,6 +402,15 @@ pub fn print_event(e: Event) {
                 style(filename).cyan(),
             );
         }
+        Event::ParentNotFound(entry_name, parent_name, p, root) => {
+            warn!(
+                root,
+                "Could not find parent \"{}\" of \"{}\" in DAT {}",
+                parent_name,
+                entry_name,
+                p.display()
+            );
+        }
         Event::ParseEntryError(err, root) => match err {
             DatError::NameError(NameError::ParseError(convention, filename)) => {
                 warn!(
@@ -1,7 +1,13 @@
-mod database;
+mod archive;
+mod audit;
+mod diff;
+mod fixdat;
 mod ingest;
 mod log;
+mod rename;
+mod selection;
 mod sortrules;
+mod validate;
 
 use shiratsu_stone::{
     PlatformId, StonePlatforms
@@ -9,7 +15,8 @@ use shiratsu_stone::{
 
 use shiratsu_dat::{
     *,
-    {nointro::*, redump::*, tosec::*, dats_site::*, opengood::*}
+    {nointro::*, redump::*, tosec::*, dats_site::*, opengood::*},
+    selection::{one_game_one_rom, Grouping, Priority},
 };
 
 use anyhow::{anyhow, Error, Result};
@@ -17,13 +24,14 @@ use anyhow::{anyhow, Error, Result};
 use slog::{o, Drain, Logger};
 
 use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs::{create_dir, File, OpenOptions};
-use std::io::{self, BufRead, BufReader, ErrorKind, Seek, SeekFrom};
-use std::path::Path;
-use std::{ffi::OsStr, time::Instant};
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::{ffi::OsStr, time::{Instant, SystemTime, UNIX_EPOCH}};
 
-use database::{DatabaseError, ShiratsuDatabase};
+use shiratsu::database::{DatabaseError, ShiratsuDatabase};
 
 use console::style;
 use indicatif::ProgressBar;
@@ -31,41 +39,56 @@ use indicatif::ProgressBar;
 use lazy_static_include::*;
 
 use glob::glob_with;
-use glob::MatchOptions;
+use glob::{MatchOptions, Pattern};
+use walkdir::WalkDir;
+use ingest::DatFile;
 use shiratsu_dat::GameEntry;
 
-type ParseResult<T> = std::result::Result<T, DatError>;
-
 fn get_entries<R: BufRead + Seek>(
     mut reader: R,
-) -> Result<Option<(Vec<ParseResult<GameEntry>>, &'static str)>> {
+) -> Result<Option<(DatReader<R>, &'static str)>> {
     reader.seek(SeekFrom::Start(0))?;
-    match GameEntry::try_from_nointro_buf(reader.by_ref()) {
-        Ok(entries) => return Ok(Some((entries, "No-Intro"))),
+    match GameEntry::try_iter_from_nointro_buf(reader.by_ref()) {
+        Ok(_) => {
+            reader.seek(SeekFrom::Start(0))?;
+            return Ok(Some((GameEntry::try_iter_from_nointro_buf(reader)?, "No-Intro")));
+        }
         Err(DatError::HeaderMismatchError(_, _)) => {}
         Err(err) => return Err(Error::new(err)),
     }
     reader.seek(SeekFrom::Start(0))?;
-    match GameEntry::try_from_redump_buf(reader.by_ref()) {
-        Ok(entries) => return Ok(Some((entries, "Redump"))),
+    match GameEntry::try_iter_from_redump_buf(reader.by_ref()) {
+        Ok(_) => {
+            reader.seek(SeekFrom::Start(0))?;
+            return Ok(Some((GameEntry::try_iter_from_redump_buf(reader)?, "Redump")));
+        }
         Err(DatError::HeaderMismatchError(_, _)) => {}
         Err(err) => return Err(Error::new(err)),
     }
     reader.seek(SeekFrom::Start(0))?;
-    match GameEntry::try_from_tosec_buf(reader.by_ref()) {
-        Ok(entries) => return Ok(Some((entries, "TOSEC"))),
+    match GameEntry::try_iter_from_tosec_buf(reader.by_ref()) {
+        Ok(_) => {
+            reader.seek(SeekFrom::Start(0))?;
+            return Ok(Some((GameEntry::try_iter_from_tosec_buf(reader)?, "TOSEC")));
+        }
         Err(DatError::HeaderMismatchError(_, _)) => {}
         Err(err) => return Err(Error::new(err)),
     }
     reader.seek(SeekFrom::Start(0))?;
-    match GameEntry::try_from_opengood_buf(reader.by_ref()) {
-        Ok(entries) => return Ok(Some((entries, "OpenGood"))),
+    match GameEntry::try_iter_from_opengood_buf(reader.by_ref()) {
+        Ok(_) => {
+            reader.seek(SeekFrom::Start(0))?;
+            return Ok(Some((GameEntry::try_iter_from_opengood_buf(reader)?, "OpenGood")));
+        }
         Err(DatError::HeaderMismatchError(_, _)) => {}
         Err(err) => return Err(Error::new(err)),
     }
     reader.seek(SeekFrom::Start(0))?;
-    match GameEntry::try_from_dats_site_buf(reader.by_ref()) {
-        Ok(entries) => return Ok(Some((entries, "dats.site"))),
+    match GameEntry::try_iter_from_dats_site_buf(reader.by_ref()) {
+        Ok(_) => {
+            reader.seek(SeekFrom::Start(0))?;
+            return Ok(Some((GameEntry::try_iter_from_dats_site_buf(reader)?, "dats.site")));
+        }
         Err(DatError::HeaderMismatchError(_, _)) => {}
         Err(err) => return Err(Error::new(err)),
     }
@@ -111,6 +134,7 @@ fn setup_logging<T: AsRef<Path>>(log_path: T, file_log_path: T) -> (slog::Logger
 }
 
 lazy_static_include_str!(SORTING_RULES, "../sortrules.yml");
+lazy_static_include_str!(SELECTION_RULES, "../1g1r.yml");
 
 pub enum Event<'a> {
     CreatingFolderStructure,
@@ -121,12 +145,15 @@ pub enum Event<'a> {
     CreateFoldersSuccess,
     DatabaseSavePathAlreadyExists(&'a Path),
     GeneratingDatabase(&'a Path, &'a Logger),
+    UpdatingDatabase(&'a Path, &'a Logger),
+    RemovedDat(&'a str, usize, &'a Logger),
+    UnchangedDats(usize),
     FoundDatFile(
         &'a ProgressBar,
         &'a Path,
-        u64,
         &'a PlatformId,
         &'a str,
+        Option<&'a DatHeader>,
         &'a Logger,
         &'a Logger,
     ),
@@ -146,8 +173,33 @@ pub enum Event<'a> {
     LoadExternalSortingRules,
     LoadedSortingRules(&'a str),
     SortedFile(&'a std::ffi::OsStr, &'a PlatformId),
+    MovedSortedArchive(&'a Path),
     SortingSuccess(usize, u64),
+    LoadInternalSelectionRules,
+    LoadExternalSelectionRules,
+    LoadedSelectionRules(&'a str),
+    SelectionSuccess(&'a Path, usize, usize, u64),
+    AuditingDirectory(&'a Path, &'a Path),
+    HashedFile(&'a ProgressBar, &'a audit::LocalFile),
+    UnreadableFile(&'a ProgressBar, &'a Path, &'a anyhow::Error),
+    HashingSuccess(&'a ProgressBar, usize),
+    AuditedPlatform(&'a str, &'a audit::PlatformAudit<'a>),
+    AuditSuccess(&'a Path, usize, usize, u64),
+    WroteFixdat(&'a Path, usize, usize),
+    FixdatSuccess(usize, usize, u64),
+    ValidatingDatabase(&'a Path),
+    ViolatedSpecification(&'a validate::Violation),
+    ValidationSuccess(&'a Path),
+    DiffingDatabases(&'a Path, &'a Path),
+    DiffSuccess(&'a diff::Diff, &'a Path, &'a Path),
+    PlannedRename(&'a rename::Operation),
+    SkippedRename(&'a Path, &'a rename::SkipReason),
+    Renamed(&'a rename::Operation),
+    RenameSuccess(usize, usize, Option<&'a Path>, u64),
+    UndidRename(&'a rename::Operation),
+    UndoRenameSuccess(usize, &'a Path),
     NoEntriesFound(&'a OsStr, &'a Logger),
+    ParentNotFound(&'a str, &'a str, &'a Path, &'a Logger),
 }
 
 fn create_folders<F>(event_fn: F) -> Result<()>
@@ -184,7 +236,133 @@ where
     Ok(())
 }
 
-fn create_db<S: AsRef<str>, F>(save_path: S, event_fn: F) -> Result<()>
+/// A DAT file parsed on a worker thread, waiting to be added to the database.
+struct ParsedDatFile {
+    platform_id: &'static PlatformId,
+    dat: DatFile,
+    sha1: String,
+    parsed: Option<(ParsedDat, &'static str)>,
+}
+
+fn parse_dat_file((platform_id, dat): (&'static PlatformId, DatFile)) -> Result<ParsedDatFile> {
+    let (sha1, parsed) = match &dat.inner {
+        // DATs in archives are decompressed and hashed in one pass, since the parsers need to seek.
+        Some(inner) => {
+            let (data, sha1) = archive::read_file(&dat.path, inner, |reader| Ok(ingest::read_to_end_hashed(reader)?))?;
+            let parsed = get_entries(io::Cursor::new(data))?.map(|(entries, source)| (entries.into(), source));
+            (sha1, parsed)
+        }
+        None => {
+            let reader = BufReader::new(File::open(&dat.path)?);
+            let parsed = get_entries(reader)?.map(|(entries, source)| (entries.into(), source));
+            (ingest::hash_file(&dat.path)?, parsed)
+        }
+    };
+    Ok(ParsedDatFile {
+        platform_id,
+        sha1,
+        dat,
+        parsed,
+    })
+}
+
+fn add_dat_file<F>(
+    db: &mut ShiratsuDatabase,
+    parsed: ParsedDatFile,
+    priority: Option<&Priority>,
+    root: &Logger,
+    filelog: &Logger,
+    event_fn: &F,
+) -> Result<()>
+where
+    F: Fn(Event) -> (),
+{
+    let ParsedDatFile {
+        platform_id,
+        dat: dat_file,
+        sha1,
+        parsed,
+    } = parsed;
+    let (dat, source) = match parsed {
+        Some(parsed) => parsed,
+        None => {
+            event_fn(Event::NoEntriesFound(dat_file.file_name(), root));
+            return Ok(());
+        }
+    };
+
+    let dat_id = db.add_dat(&dat_file.file_name().to_string_lossy(), dat.header(), &sha1, source)?;
+    let dat_path = dat_file.display_path();
+
+    let pb = ProgressBar::new_spinner();
+    event_fn(Event::FoundDatFile(
+        &pb,
+        &dat_path,
+        platform_id,
+        source,
+        dat.header(),
+        root,
+        filelog,
+    ));
+
+    let mut parse_errors = Vec::new();
+    let mut entries = Vec::new();
+    for game in dat {
+        match game {
+            Ok(game) => entries.push(game),
+            Err(err) => parse_errors.push(err),
+        }
+    }
+    // 1G1R selection needs every entry in the DAT before any can be added.
+    let selected = match priority {
+        Some(priority) => one_game_one_rom(&entries, Grouping::infer(&entries), priority),
+        None => entries.iter().collect(),
+    };
+
+    let mut game_ids = HashMap::new();
+    let mut clones = Vec::new();
+    db.add_entries(selected.iter().copied(), platform_id, dat_id, |game, game_id| {
+        event_fn(Event::ProcessEntry(
+            &pb,
+            platform_id,
+            &dat_path,
+            game.entry_name(),
+            root,
+        ));
+        if let Some(parent_name) = game.parent_name() {
+            clones.push((game_id, game.entry_name().to_string(), parent_name.to_string()));
+        }
+        game_ids.insert(game.entry_name().to_string(), game_id);
+        event_fn(Event::ProcessEntrySuccess(&pb));
+    })?;
+    let count = selected.len();
+
+    event_fn(Event::DatProcessingSuccess(
+        &pb,
+        platform_id,
+        &dat_path,
+        count,
+        root,
+    ));
+
+    // Parents are only resolved within the same DAT.
+    let entry_names = entries.iter().map(|e| e.entry_name()).collect::<HashSet<_>>();
+    for (game_id, entry_name, parent_name) in clones.iter() {
+        match game_ids.get(parent_name) {
+            Some(parent_id) => db.add_clone_of(*game_id, *parent_id)?,
+            // A parent left out by 1G1R selection is expected to be missing.
+            None if entry_names.contains(parent_name.as_str()) => {}
+            None => event_fn(Event::ParentNotFound(entry_name, parent_name, &dat_path, root)),
+        }
+    }
+
+    for error in parse_errors.iter() {
+        event_fn(Event::ParseEntryError(error, root));
+    }
+    Ok(())
+}
+
+fn create_db<S: AsRef<str>, F>(save_path: S, selection: Option<(&str, &Priority)>, event_fn: F) -> Result<()>
 where
     F: Fn(Event) -> (),
 {
@@ -201,55 +379,15 @@ where
     event_fn(Event::GeneratingDatabase(&save_path, &root));
 
     let mut db = ShiratsuDatabase::new().unwrap();
-    for (platform_id, dir) in ingest::get_paths("dats").into_iter() {
-        let mut parse_errors = Vec::new();
-        let reader = BufReader::new(File::open(dir.path())?);
-        match get_entries(reader) {
-            Ok(Some((entries, source))) => {
-                let pb = ProgressBar::new(entries.len() as u64);
-                event_fn(Event::FoundDatFile(
-                    &pb,
-                    dir.path(),
-                    entries.len() as u64,
-                    platform_id,
-                    source,
-                    &root,
-                    &filelog,
-                ));
-
-                for game in entries.iter() {
-                    match game {
-                        Ok(game) => {
-                            event_fn(Event::ProcessEntry(
-                                &pb,
-                                platform_id,
-                                dir.path(),
-                                game.entry_name(),
-                                &root,
-                            ));
-                            db.add_entry(game, platform_id).unwrap();
-                            event_fn(Event::ProcessEntrySuccess(&pb));
-                        }
-                        Err(err) => parse_errors.push(Event::ParseEntryError(err, &root)),
-                    }
-                }
-
-                event_fn(Event::DatProcessingSuccess(
-                    &pb,
-                    platform_id,
-                    dir.path(),
-                    entries.len(),
-                    &root,
-                ));
-
-                for error in parse_errors.into_iter() {
-                    event_fn(error);
-                }
-            }
-            Ok(None) => event_fn(Event::NoEntriesFound(dir.file_name(), &root)),
-            Err(err) => return Err(err),
-        }
+    if let Some((rules, _)) = selection {
+        db = db.with_selection(rules);
     }
+    let priority = selection.map(|(_, priority)| priority);
+    // DAT files are parsed in parallel, but added to the database in order
+    // so that game IDs are the same between builds.
+    ingest::par_map_ordered(ingest::get_paths("dats")?, parse_dat_file, |parsed| {
+        add_dat_file(&mut db, parsed?, priority, &root, &filelog, &event_fn)
+    })?;
 
     match db.save(save_path, Some(log::process_duration)) {
         Ok((uuid, time)) => {
@@ -267,11 +405,89 @@ where
             Err(match err {
                 DatabaseError::IOError(err) => anyhow::Error::new(err),
                 DatabaseError::SqliteError(err) => anyhow::Error::new(err),
+                err => anyhow::Error::new(err),
             })
         }
     }
 }
 
+fn update_db<F>(args: &[String], event_fn: F) -> Result<()>
+where
+    F: Fn(Event) -> (),
+{
+    let now = Instant::now();
+    let db_path = match args {
+        [db_path] => Path::new(db_path),
+        _ => return Err(anyhow!("Usage: shiratsu update <database>")),
+    };
+    if !db_path.exists() {
+        return Err(anyhow::Error::new(io::Error::new(
+            ErrorKind::NotFound,
+            format!("{} does not exist.", db_path.display()),
+        )));
+    }
+    let (root, filelog) = setup_logging(format!("{}.log", db_path.display()), format!("{}.inputs.log", db_path.display()));
+    event_fn(Event::UpdatingDatabase(db_path, &root));
+
+    let mut db = ShiratsuDatabase::open(db_path)?;
+    // DATs added to a 1G1R database are selected with the rules the database was generated with.
+    let priority = match db.selection() {
+        Some(rules) => {
+            let priority = selection::load_priority(rules)?;
+            event_fn(Event::LoadedSelectionRules("the database"));
+            Some(priority)
+        }
+        None => None,
+    };
+
+    // A DAT is unchanged if a DAT with the same file name and content hash is already in the database.
+    // Every other DAT in the database is removed, and every other DAT on disk is added.
+    let dats = ingest::get_paths("dats")?;
+    let hashes = ingest::hash_all(&dats)?;
+    let paths = dats
+        .into_iter()
+        .zip(hashes)
+        .map(|((platform_id, dat), sha1)| ((dat.file_name().to_string_lossy().into_owned(), sha1), (platform_id, dat)))
+        .collect::<Vec<_>>();
+    let on_disk = paths.iter().map(|(key, _)| key).collect::<HashSet<_>>();
+    let mut unchanged = HashSet::new();
+    for (dat_id, file_name, sha1) in db.dats()? {
+        let key = (file_name, sha1);
+        if on_disk.contains(&key) {
+            unchanged.insert(key);
+        } else {
+            let removed = db.remove_dat(dat_id)?;
+            event_fn(Event::RemovedDat(&key.0, removed, &root));
+        }
+    }
+    event_fn(Event::UnchangedDats(unchanged.len()));
+
+    let changed = paths
+        .into_iter()
+        .filter(|(key, _)| !unchanged.contains(key))
+        .map(|(_, path)| path)
+        .collect::<Vec<_>>();
+    ingest::par_map_ordered(changed, parse_dat_file, |parsed| {
+        add_dat_file(&mut db, parsed?, priority.as_ref(), &root, &filelog, &event_fn)
+    })?;
+
+    match db.overwrite(db_path, Some(log::process_duration)) {
+        Ok((uuid, time)) => {
+            event_fn(Event::DbSaveSuccess(
+                db_path,
+                &uuid,
+                &time,
+                now.elapsed().as_secs(),
+            ));
+            Ok(())
+        }
+        Err(err) => {
+            event_fn(Event::DbSaveError(db_path, &root));
+            Err(anyhow::Error::new(err))
+        }
+    }
+}
+
 fn sort_dats<F>(event_fn: F) -> Result<()>
 where
     F: Fn(Event) -> (),
@@ -311,7 +527,7 @@ where
         current_dir.push(platform_id.as_ref());
         for path in rules
             .iter()
-            .flat_map(|rule| glob_with(rule, options))
+            .flat_map(|rule| glob_with(&format!("unsorted/**/{}", rule), options))
             .flat_map(|rule| rule)
             .flat_map(|result| result)
         {
@@ -326,23 +542,455 @@ where
         }
         current_dir.pop();
     }
+
+    // DATs inside archives that are left in unsorted are extracted, and the archive is then moved
+    // to sorted so that it is not extracted again, which would bring back DATs that were since replaced.
+    let patterns = rules
+        .iter()
+        .flat_map(|(platform_id, rules)| rules.iter().map(move |rule| (platform_id, rule)))
+        .map(|(platform_id, rule)| Ok((platform_id, Pattern::new(rule)?)))
+        .collect::<Result<Vec<_>>>()?;
+    for entry in WalkDir::new("unsorted").sort_by(|a, b| a.file_name().cmp(b.file_name())) {
+        let entry = entry?;
+        if !entry.file_type().is_file() || !archive::is_archive(entry.path()) {
+            continue;
+        }
+        let extracted = count;
+        archive::for_each_file(entry.path(), |name, reader| {
+            let filename = match Path::new(name).file_name() {
+                Some(filename) => filename,
+                None => return Ok(()),
+            };
+            let platform_id = patterns
+                .iter()
+                .find(|(_, pattern)| pattern.matches_with(&filename.to_string_lossy(), options))
+                .map(|(platform_id, _)| *platform_id);
+            if let Some(platform_id) = platform_id {
+                current_dir.push(platform_id.as_ref());
+                current_dir.push(filename);
+                io::copy(reader, &mut File::create(&current_dir)?)?;
+                event_fn(Event::SortedFile(filename, platform_id));
+                count += 1;
+                current_dir.pop();
+                current_dir.pop();
+            }
+            Ok(())
+        })?;
+        if count > extracted {
+            let sorted_dir = Path::new("sorted");
+            if !sorted_dir.exists() {
+                create_dir(sorted_dir)?;
+            }
+            let sorted_path = sorted_dir.join(entry.file_name());
+            std::fs::rename(entry.path(), &sorted_path)?;
+            event_fn(Event::MovedSortedArchive(&sorted_path));
+        }
+    }
     event_fn(Event::SortingSuccess(count, now.elapsed().as_secs()));
     Ok(())
 }
 
+/// Loads the 1G1R rules, returning the rules along with the priority they describe.
+fn load_selection_rules<F>(event_fn: &F) -> Result<(String, Priority)>
+where
+    F: Fn(Event) -> (),
+{
+    let rules = std::fs::read_to_string("1g1r.yml")
+        .map_or_else(|_| Cow::Borrowed(*SELECTION_RULES), |s| Cow::Owned(s));
+    let rules_src = match &rules {
+        Cow::Borrowed(_) => {
+            event_fn(Event::LoadInternalSelectionRules);
+            "internal 1G1R rules"
+        }
+        Cow::Owned(_) => {
+            event_fn(Event::LoadExternalSelectionRules);
+            "1g1r.yml"
+        }
+    };
+    let priority = selection::load_priority(&rules)?;
+    event_fn(Event::LoadedSelectionRules(rules_src));
+    Ok((rules.into_owned(), priority))
+}
+
+fn one_game_one_rom_dat<F>(input_path: &Path, output_path: &Path, priority: &Priority, event_fn: F) -> Result<()>
+where
+    F: Fn(Event) -> (),
+{
+    let now = Instant::now();
+    if output_path.exists() {
+        return Err(anyhow::Error::new(io::Error::new(
+            ErrorKind::AlreadyExists,
+            "The specified path already exists.",
+        )));
+    }
+
+    let reader = BufReader::new(File::open(input_path)?);
+    let dat: ParsedDat = match get_entries(reader)? {
+        Some((entries, _)) => entries.into(),
+        None => return Err(anyhow!("No entries found in {}.", input_path.display())),
+    };
+    let entries = dat
+        .entries()
+        .iter()
+        .filter_map(|e| e.as_ref().ok())
+        .collect::<Vec<_>>();
+    let grouping = Grouping::infer(entries.iter().copied());
+    let selected = one_game_one_rom(entries.iter().copied(), grouping, priority);
+
+    let mut writer = DatWriter::new(BufWriter::new(File::create(output_path)?), dat.header())?;
+    for entry in selected.iter() {
+        writer.write_entry(entry)?;
+    }
+    writer.finish()?.flush()?;
+
+    event_fn(Event::SelectionSuccess(
+        output_path,
+        selected.len(),
+        entries.len(),
+        now.elapsed().as_secs(),
+    ));
+    Ok(())
+}
+
+fn one_game_one_rom_cmd<F>(args: &[String], event_fn: F) -> Result<()>
+where
+    F: Fn(Event) -> (),
+{
+    let (rules, priority) = load_selection_rules(&event_fn)?;
+    match args {
+        [save_path] => create_db(save_path, Some((&rules, &priority)), event_fn),
+        [input_path, output_path] => one_game_one_rom_dat(
+            Path::new(input_path),
+            Path::new(output_path),
+            &priority,
+            event_fn,
+        ),
+        _ => Err(anyhow!(
+            "Usage: shiratsu 1g1r <save path> or shiratsu 1g1r <input DAT> <output DAT>"
+        )),
+    }
+}
+
+fn audit_dir<F>(args: &[String], event_fn: F) -> Result<()>
+where
+    F: Fn(Event) -> (),
+{
+    let now = Instant::now();
+    let (db_path, dir) = match args {
+        [db_path, dir] => (Path::new(db_path), Path::new(dir)),
+        _ => return Err(anyhow!("Usage: shiratsu audit <database> <directory>")),
+    };
+    event_fn(Event::AuditingDirectory(dir, db_path));
+
+    let index = audit::RomIndex::from_database(db_path)?;
+    let pb = ProgressBar::new_spinner();
+    let files = audit::scan_dir(
+        dir,
+        |file| event_fn(Event::HashedFile(&pb, file)),
+        |path, err| event_fn(Event::UnreadableFile(&pb, path, err)),
+    );
+    event_fn(Event::HashingSuccess(&pb, files.len()));
+
+    let audit = audit::audit(&index, files);
+    let report_path = format!("{}.audit.log", db_path.display());
+    let mut report = BufWriter::new(File::create(&report_path)?);
+    for (platform_id, platform) in audit.by_platform(&index).iter() {
+        event_fn(Event::AuditedPlatform(platform_id, platform));
+        for game in platform.games.iter() {
+            writeln!(
+                report,
+                "[{}] {} {} ({}/{})",
+                platform_id,
+                game.status().as_ref(),
+                game.entry_name,
+                game.have,
+                game.roms.len()
+            )?;
+            if game.status() == audit::GameStatus::Partial {
+                for &rom in game.roms.iter().filter(|&&rom| audit.matches[rom].is_empty()) {
+                    writeln!(report, "    missing {}", index.roms()[rom].file_name)?;
+                }
+            }
+        }
+    }
+    for &file in audit.unknown.iter() {
+        writeln!(report, "unknown {}", audit.files[file])?;
+    }
+    report.flush()?;
+
+    event_fn(Event::AuditSuccess(
+        Path::new(&report_path),
+        audit.files.len(),
+        audit.unknown.len(),
+        now.elapsed().as_secs(),
+    ));
+    Ok(())
+}
+
+fn write_fixdats<F>(args: &[String], event_fn: F) -> Result<()>
+where
+    F: Fn(Event) -> (),
+{
+    let now = Instant::now();
+    let (dir, dat_paths) = match args {
+        [dir, dat_paths @ ..] if !dat_paths.is_empty() => (Path::new(dir), dat_paths),
+        _ => return Err(anyhow!("Usage: shiratsu fixdat <directory> <DAT> [<DAT> ...]")),
+    };
+
+    let mut dats = Vec::new();
+    for dat_path in dat_paths.iter().map(Path::new) {
+        let file_name = dat_path
+            .file_name()
+            .ok_or_else(|| anyhow!("{} is not a DAT file.", dat_path.display()))?;
+        let output_path = PathBuf::from(format!("fix_{}", file_name.to_string_lossy()));
+        if output_path.exists() {
+            return Err(anyhow::Error::new(io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} already exists.", output_path.display()),
+            )));
+        }
+        let reader = BufReader::new(File::open(dat_path)?);
+        let dat: ParsedDat = match get_entries(reader)? {
+            Some((entries, _)) => entries.into(),
+            None => return Err(anyhow!("No entries found in {}.", dat_path.display())),
+        };
+        event_fn(Event::AuditingDirectory(dir, dat_path));
+        dats.push((output_path, file_name.to_string_lossy().into_owned(), dat));
+    }
+
+    let mut index = audit::RomIndex::default();
+    let dats = dats
+        .iter()
+        .map(|(output_path, file_name, dat)| {
+            let entries = dat
+                .entries()
+                .iter()
+                .filter_map(|e| e.as_ref().ok())
+                .collect::<Vec<_>>();
+            let dat_name = dat
+                .header()
+                .and_then(|h| h.name())
+                .unwrap_or(file_name);
+            let indexed = fixdat::index_entries(&mut index, dat_name, entries.iter().copied());
+            let header = dat.header().cloned().unwrap_or_default().renamed(
+                &format!("Fix {}", dat_name),
+                &format!("ROMs missing from {} in {}", dat_name, dir.display()),
+            );
+            (output_path, header, entries, indexed)
+        })
+        .collect::<Vec<_>>();
+
+    let pb = ProgressBar::new_spinner();
+    let files = audit::scan_dir(
+        dir,
+        |file| event_fn(Event::HashedFile(&pb, file)),
+        |path, err| event_fn(Event::UnreadableFile(&pb, path, err)),
+    );
+    event_fn(Event::HashingSuccess(&pb, files.len()));
+    let audit = audit::audit(&index, files);
+
+    let mut offset = 0;
+    let mut total_missing = 0;
+    for (output_path, header, entries, indexed) in dats.iter() {
+        let missing = fixdat::missing_roms(entries, &audit.matches[offset..offset + indexed]);
+        offset += indexed;
+
+        let mut writer = DatWriter::new(BufWriter::new(File::create(output_path)?), Some(header))?;
+        for (entry, roms) in missing.iter() {
+            writer.write_entry_with_roms(entry, roms.iter().copied())?;
+        }
+        writer.finish()?.flush()?;
+        event_fn(Event::WroteFixdat(output_path, missing.len(), entries.len()));
+        total_missing += missing.len();
+    }
+
+    event_fn(Event::FixdatSuccess(
+        dats.len(),
+        total_missing,
+        now.elapsed().as_secs(),
+    ));
+    Ok(())
+}
+
+fn rename_files<F>(args: &[String], event_fn: F) -> Result<()>
+where
+    F: Fn(Event) -> (),
+{
+    let now = Instant::now();
+    if let [flag, journal_path] = args {
+        if flag == "--undo" {
+            let journal_path = Path::new(journal_path);
+            let operations = rename::read_journal(journal_path)?;
+            rename::undo(&operations, |op| event_fn(Event::UndidRename(op)))?;
+            event_fn(Event::UndoRenameSuccess(operations.len(), journal_path));
+            return Ok(());
+        }
+    }
+
+    let mut mode = rename::RenameMode::Move;
+    let mut dry_run = false;
+    let mut paths = Vec::new();
+    for arg in args.iter() {
+        match arg.as_str() {
+            "--copy" => mode = rename::RenameMode::Copy,
+            "--link" => mode = rename::RenameMode::HardLink,
+            "--dry-run" => dry_run = true,
+            path => paths.push(Path::new(path)),
+        }
+    }
+    let (db_path, dir, target) = match paths[..] {
+        [db_path, dir] => (db_path, dir, None),
+        [db_path, dir, target] => (db_path, dir, Some(target)),
+        _ => {
+            return Err(anyhow!(
+                "Usage: shiratsu rename <database> <directory> [<target>] [--copy | --link] [--dry-run]\n       shiratsu rename --undo <journal>"
+            ))
+        }
+    };
+    if target.is_none() && mode != rename::RenameMode::Move {
+        return Err(anyhow!("A target directory is required to copy or link files."));
+    }
+
+    event_fn(Event::AuditingDirectory(dir, db_path));
+    let index = audit::RomIndex::from_database(db_path)?;
+    let pb = ProgressBar::new_spinner();
+    let files = audit::scan_dir(
+        dir,
+        |file| event_fn(Event::HashedFile(&pb, file)),
+        |path, err| event_fn(Event::UnreadableFile(&pb, path, err)),
+    );
+    event_fn(Event::HashingSuccess(&pb, files.len()));
+
+    let audit = audit::audit(&index, files);
+    let plan = rename::plan(&index, &audit, target, mode);
+    for (path, reason) in plan.skipped.iter() {
+        event_fn(Event::SkippedRename(path, reason));
+    }
+
+    if dry_run {
+        for op in plan.operations.iter() {
+            event_fn(Event::PlannedRename(op));
+        }
+        event_fn(Event::RenameSuccess(
+            plan.operations.len(),
+            plan.skipped.len(),
+            None,
+            now.elapsed().as_secs(),
+        ));
+        return Ok(());
+    }
+
+    let journal_path = format!(
+        "shiratsu.rename.{}.journal",
+        SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs()
+    );
+    let mut journal = File::create(&journal_path)?;
+    rename::execute(&plan, &mut journal, |op| event_fn(Event::Renamed(op)))?;
+    event_fn(Event::RenameSuccess(
+        plan.operations.len(),
+        plan.skipped.len(),
+        Some(Path::new(&journal_path)),
+        now.elapsed().as_secs(),
+    ));
+    Ok(())
+}
+
+fn validate_db<F>(args: &[String], event_fn: F) -> Result<()>
+where
+    F: Fn(Event) -> (),
+{
+    let db_path = match args {
+        [db_path] => Path::new(db_path),
+        _ => return Err(anyhow!("Usage: shiratsu validate <database>")),
+    };
+    if !db_path.exists() {
+        return Err(anyhow::Error::new(io::Error::new(
+            ErrorKind::NotFound,
+            format!("{} does not exist.", db_path.display()),
+        )));
+    }
+    event_fn(Event::ValidatingDatabase(db_path));
+
+    let conn = rusqlite::Connection::open_with_flags(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let report = validate::validate(&conn)?;
+    for violation in report.violations.iter() {
+        event_fn(Event::ViolatedSpecification(violation));
+    }
+
+    let report_path = format!("{}.validate.json", db_path.display());
+    let mut writer = BufWriter::new(File::create(&report_path)?);
+    serde_json::to_writer_pretty(&mut writer, &report)?;
+    writer.flush()?;
+
+    if report.violations.is_empty() {
+        event_fn(Event::ValidationSuccess(Path::new(&report_path)));
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "{} does not conform to the specification, report written to {}",
+            db_path.display(),
+            report_path
+        ))
+    }
+}
+
+fn diff_dbs<F>(args: &[String], event_fn: F) -> Result<()>
+where
+    F: Fn(Event) -> (),
+{
+    let (old_path, new_path) = match args {
+        [old_path, new_path] => (Path::new(old_path), Path::new(new_path)),
+        _ => return Err(anyhow!("Usage: shiratsu diff <old database> <new database>")),
+    };
+    for db_path in [old_path, new_path].iter() {
+        if !db_path.exists() {
+            return Err(anyhow::Error::new(io::Error::new(
+                ErrorKind::NotFound,
+                format!("{} does not exist.", db_path.display()),
+            )));
+        }
+    }
+    event_fn(Event::DiffingDatabases(old_path, new_path));
+
+    let diff = diff::diff(
+        &diff::Release::from_database(old_path)?,
+        &diff::Release::from_database(new_path)?,
+    );
+
+    let text_path = format!("{}.diff.txt", new_path.display());
+    let mut writer = BufWriter::new(File::create(&text_path)?);
+    diff.write_text(&mut writer)?;
+    writer.flush()?;
+
+    let json_path = format!("{}.diff.json", new_path.display());
+    let mut writer = BufWriter::new(File::create(&json_path)?);
+    serde_json::to_writer_pretty(&mut writer, &diff)?;
+    writer.flush()?;
+
+    event_fn(Event::DiffSuccess(&diff, Path::new(&text_path), Path::new(&json_path)));
+    Ok(())
+}
+
 fn run_app<F>(event_fn: F) -> Result<()>
 where
     F: Fn(Event) -> (),
 {
-    let args = env::args().skip(1).take(1).next();
-    let command = args.ok_or(io::Error::new(
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let command = args.first().ok_or(io::Error::new(
         ErrorKind::NotFound,
         "No save path was specified.",
     ))?;
 
     match command.as_str() {
         "sort" => sort_dats(event_fn),
-        save_path => create_db(save_path, event_fn),
+        "1g1r" => one_game_one_rom_cmd(&args[1..], event_fn),
+        "audit" => audit_dir(&args[1..], event_fn),
+        "rename" => rename_files(&args[1..], event_fn),
+        "fixdat" => write_fixdats(&args[1..], event_fn),
+        "validate" => validate_db(&args[1..], event_fn),
+        "diff" => diff_dbs(&args[1..], event_fn),
+        "update" => update_db(&args[1..], event_fn),
+        save_path => create_db(save_path, None, event_fn),
     }
 }
 
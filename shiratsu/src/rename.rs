@@ -0,0 +1,336 @@
+use crate::audit::{Audit, RomIndex};
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// How a matched file is given its canonical name.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RenameMode {
+    /// The file is renamed, or moved into the target tree.
+    Move,
+    /// The file is hard-linked into the target tree.
+    HardLink,
+    /// The file is copied into the target tree.
+    Copy,
+}
+
+impl AsRef<str> for RenameMode {
+    fn as_ref(&self) -> &str {
+        match self {
+            RenameMode::Move => "move",
+            RenameMode::HardLink => "link",
+            RenameMode::Copy => "copy",
+        }
+    }
+}
+
+impl RenameMode {
+    fn from_str(mode: &str) -> Option<RenameMode> {
+        match mode {
+            "move" => Some(RenameMode::Move),
+            "link" => Some(RenameMode::HardLink),
+            "copy" => Some(RenameMode::Copy),
+            _ => None,
+        }
+    }
+}
+
+/// A single planned rename.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Operation {
+    pub mode: RenameMode,
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Why a matched file was left untouched.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SkipReason {
+    /// The file is inside an archive and can not be renamed by itself.
+    InArchive,
+    /// The file already has its canonical name.
+    AlreadyNamed,
+    /// Another local file already matched the same ROM.
+    Conflict(PathBuf),
+    /// The destination already exists, or another file is planned to be renamed to it.
+    DestinationExists(PathBuf),
+    /// The canonical name of the ROM would place the file outside of its directory.
+    UnsafeName(String),
+}
+
+#[derive(Debug, Default)]
+pub struct Plan {
+    pub operations: Vec<Operation>,
+    pub skipped: Vec<(PathBuf, SkipReason)>,
+}
+
+/// Plans the renames of every matched local file to the canonical file name of the ROM it matches.
+///
+/// Without a target, files are renamed in place. With a target, files are laid out as
+/// `<PLATFORM_ID>/<entry_name>/<file_name>` under the target. When moving, a file matching more
+/// than one ROM is only moved to the first ROM it matches.
+pub fn plan(index: &RomIndex, audit: &Audit, target: Option<&Path>, mode: RenameMode) -> Plan {
+    let mut plan = Plan::default();
+    let mut used_files = HashSet::new();
+    let mut destinations = HashSet::new();
+
+    for (rom_index, files) in audit.matches.iter().enumerate() {
+        let rom = &index.roms()[rom_index];
+        let mut files = files.iter().map(|&f| &audit.files[f]);
+        let file = match files.next() {
+            Some(file) => file,
+            None => continue,
+        };
+        for other in files {
+            plan.skipped
+                .push((other.path.clone(), SkipReason::Conflict(file.path.clone())));
+        }
+
+        if file.inner.is_some() {
+            plan.skipped.push((file.path.clone(), SkipReason::InArchive));
+            continue;
+        }
+        if mode == RenameMode::Move && !used_files.insert(&file.path) {
+            continue;
+        }
+
+        // Only the names that are joined into the destination need to be safe.
+        let names = match target {
+            Some(_) => vec![&rom.platform_id, &rom.entry_name, &rom.file_name],
+            None => vec![&rom.file_name],
+        };
+        if let Some(name) = names.iter().find(|name| !is_safe_name(name)) {
+            plan.skipped
+                .push((file.path.clone(), SkipReason::UnsafeName(name.to_string())));
+            continue;
+        }
+
+        let to = match target {
+            Some(target) => target
+                .join(&rom.platform_id)
+                .join(&rom.entry_name)
+                .join(&rom.file_name),
+            None => file
+                .path
+                .parent()
+                .unwrap_or_else(|| Path::new(""))
+                .join(&rom.file_name),
+        };
+
+        if to == file.path {
+            plan.skipped.push((file.path.clone(), SkipReason::AlreadyNamed));
+        } else if to.exists() || !destinations.insert(to.clone()) {
+            plan.skipped
+                .push((file.path.clone(), SkipReason::DestinationExists(to)));
+        } else {
+            plan.operations.push(Operation {
+                mode,
+                from: file.path.clone(),
+                to,
+            });
+        }
+    }
+    plan
+}
+
+/// Whether the name is a relative path that stays within the directory it is joined to.
+fn is_safe_name(name: &str) -> bool {
+    let path = Path::new(name);
+    path.components().next().is_some() && path.components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// Runs every operation of the plan, recording each to the journal as soon as it is done
+/// so that an interrupted run can still be undone.
+pub fn execute<W: Write, F: Fn(&Operation)>(plan: &Plan, journal: &mut W, on_done: F) -> io::Result<()> {
+    for op in plan.operations.iter() {
+        if op.to.exists() {
+            return Err(io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} already exists.", op.to.display()),
+            ));
+        }
+        if let Some(parent) = op.to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        match op.mode {
+            RenameMode::Move => fs::rename(&op.from, &op.to)?,
+            RenameMode::HardLink => fs::hard_link(&op.from, &op.to)?,
+            RenameMode::Copy => fs::copy(&op.from, &op.to).map(|_| ())?,
+        }
+        writeln!(
+            journal,
+            "{}\t{}\t{}",
+            op.mode.as_ref(),
+            op.from.display(),
+            op.to.display()
+        )?;
+        journal.flush()?;
+        on_done(op);
+    }
+    Ok(())
+}
+
+/// Reads the operations recorded in a journal, in the order they were done.
+pub fn read_journal<T: AsRef<Path>>(path: T) -> io::Result<Vec<Operation>> {
+    let mut operations = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        let mut fields = line.splitn(3, '\t');
+        match (
+            fields.next().and_then(RenameMode::from_str),
+            fields.next(),
+            fields.next(),
+        ) {
+            (Some(mode), Some(from), Some(to)) => operations.push(Operation {
+                mode,
+                from: PathBuf::from(from),
+                to: PathBuf::from(to),
+            }),
+            _ => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Malformed journal entry {}", line),
+                ))
+            }
+        }
+    }
+    Ok(operations)
+}
+
+/// Undoes the operations of a journal in reverse order.
+///
+/// Moved files are moved back, and linked or copied files are removed.
+pub fn undo<F: Fn(&Operation)>(operations: &[Operation], on_done: F) -> io::Result<()> {
+    for op in operations.iter().rev() {
+        match op.mode {
+            RenameMode::Move => {
+                if op.from.exists() {
+                    return Err(io::Error::new(
+                        ErrorKind::AlreadyExists,
+                        format!("{} already exists.", op.from.display()),
+                    ));
+                }
+                fs::rename(&op.to, &op.from)?
+            }
+            RenameMode::HardLink | RenameMode::Copy => fs::remove_file(&op.to)?,
+        }
+        on_done(op);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{audit, hash_reader, KnownRom, LocalFile};
+
+    fn rom(entry_name: &str, file_name: &str, data: &[u8]) -> KnownRom {
+        let hashes = hash_reader(data).unwrap();
+        KnownRom {
+            platform_id: "NINTENDO_GB".to_string(),
+            entry_name: entry_name.to_string(),
+            file_name: file_name.to_string(),
+            size: hashes.size,
+            crc: Some(hashes.crc),
+            md5: Some(hashes.md5),
+            sha1: Some(hashes.sha1),
+            sha256: None,
+            xxh3: None,
+        }
+    }
+
+    fn local(path: &str, data: &[u8]) -> LocalFile {
+        LocalFile {
+            path: PathBuf::from(path),
+            inner: None,
+            hashes: hash_reader(data).unwrap(),
+        }
+    }
+
+    #[test]
+    fn plans_renames_and_conflicts() {
+        let mut index = RomIndex::default();
+        index.push(rom("Tetris (World)", "Tetris (World).gb", b"tetris"));
+        index.push(rom("Dr. Mario (World)", "Dr. Mario (World).gb", b"dr. mario"));
+        let audit = audit(
+            &index,
+            vec![
+                local("roms/tetris.gb", b"tetris"),
+                local("roms/tetris (copy).gb", b"tetris"),
+                local("roms/Dr. Mario (World).gb", b"dr. mario"),
+            ],
+        );
+
+        let plan = plan(&index, &audit, None, RenameMode::Move);
+        assert_eq!(
+            vec![Operation {
+                mode: RenameMode::Move,
+                from: PathBuf::from("roms/tetris.gb"),
+                to: PathBuf::from("roms/Tetris (World).gb"),
+            }],
+            plan.operations
+        );
+        assert_eq!(
+            vec![
+                (
+                    PathBuf::from("roms/tetris (copy).gb"),
+                    SkipReason::Conflict(PathBuf::from("roms/tetris.gb"))
+                ),
+                (PathBuf::from("roms/Dr. Mario (World).gb"), SkipReason::AlreadyNamed),
+            ],
+            plan.skipped
+        );
+    }
+
+    #[test]
+    fn plans_target_tree() {
+        let mut index = RomIndex::default();
+        index.push(rom("Tetris (World)", "Tetris (World).gb", b"tetris"));
+        let audit = audit(&index, vec![local("roms/tetris.gb", b"tetris")]);
+        let plan = plan(&index, &audit, Some(Path::new("out")), RenameMode::Copy);
+        assert_eq!(
+            PathBuf::from("out/NINTENDO_GB/Tetris (World)/Tetris (World).gb"),
+            plan.operations[0].to
+        );
+    }
+
+    #[test]
+    fn skips_unsafe_names() {
+        let mut index = RomIndex::default();
+        index.push(rom("Tetris (World)", "../../Tetris (World).gb", b"tetris"));
+        index.push(rom("/etc", "Dr. Mario (World).gb", b"dr. mario"));
+        let audit = audit(
+            &index,
+            vec![local("roms/tetris.gb", b"tetris"), local("roms/drmario.gb", b"dr. mario")],
+        );
+        let plan = plan(&index, &audit, Some(Path::new("out")), RenameMode::Copy);
+        assert!(plan.operations.is_empty());
+        assert_eq!(
+            vec![
+                (
+                    PathBuf::from("roms/tetris.gb"),
+                    SkipReason::UnsafeName("../../Tetris (World).gb".to_string())
+                ),
+                (PathBuf::from("roms/drmario.gb"), SkipReason::UnsafeName("/etc".to_string())),
+            ],
+            plan.skipped
+        );
+
+        // Renaming in place only uses the file name, so the platform ID does not matter.
+        let plan = super::plan(&index, &audit, None, RenameMode::Move);
+        assert_eq!(1, plan.operations.len());
+        assert_eq!(PathBuf::from("roms/Dr. Mario (World).gb"), plan.operations[0].to);
+        assert_eq!(
+            vec![(
+                PathBuf::from("roms/tetris.gb"),
+                SkipReason::UnsafeName("../../Tetris (World).gb".to_string())
+            )],
+            plan.skipped
+        );
+    }
+}
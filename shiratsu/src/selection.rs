@@ -0,0 +1,111 @@
+use serde::Deserialize;
+
+use shiratsu_dat::selection::Priority;
+use shiratsu_dat::DevelopmentStatus;
+use shiratsu_naming::region::Region;
+
+use anyhow::{anyhow, Result};
+
+#[derive(Debug, Deserialize)]
+struct SelectionRules {
+    #[serde(default)]
+    regions: Vec<String>,
+    #[serde(default)]
+    languages: Vec<String>,
+    #[serde(default)]
+    statuses: Vec<String>,
+    prefer_latest_version: Option<bool>,
+    demos: Option<bool>,
+    unlicensed: Option<bool>,
+    system: Option<bool>,
+}
+
+pub fn load_priority<S: AsRef<str>>(yaml_str: S) -> Result<Priority> {
+    let rules: SelectionRules = serde_yaml::from_str(yaml_str.as_ref())?;
+    let mut priority = Priority::default();
+
+    if !rules.regions.is_empty() {
+        // A region name such as World that stands for several regions is kept as a set,
+        // so that it only matches entries released in all of them.
+        let mut regions = Vec::new();
+        for region in rules.regions.iter() {
+            let set = Region::from_region_string(region)
+                .into_iter()
+                .filter(|r| *r != Region::Unknown)
+                .collect::<Vec<_>>();
+            if set.is_empty() {
+                return Err(anyhow!("Unknown region \"{}\".", region));
+            }
+            if !regions.contains(&set) {
+                regions.push(set);
+            }
+        }
+        priority = priority.with_region_sets(regions);
+    }
+
+    if !rules.languages.is_empty() {
+        priority = priority.with_languages(rules.languages.iter().map(|l| l.to_ascii_lowercase()).collect());
+    }
+
+    if !rules.statuses.is_empty() {
+        let statuses = rules
+            .statuses
+            .iter()
+            .map(|s| match s.as_str() {
+                "release" => Ok(DevelopmentStatus::Release),
+                "prerelease" => Ok(DevelopmentStatus::Prerelease),
+                "prototype" => Ok(DevelopmentStatus::Prototype),
+                s => Err(anyhow!("Unknown development status \"{}\".", s)),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        priority = priority.with_statuses(statuses);
+    }
+
+    if let Some(prefer) = rules.prefer_latest_version {
+        priority = priority.prefer_latest_version(prefer);
+    }
+    if let Some(allow) = rules.demos {
+        priority = priority.allow_demos(allow);
+    }
+    if let Some(allow) = rules.unlicensed {
+        priority = priority.allow_unlicensed(allow);
+    }
+    if let Some(allow) = rules.system {
+        priority = priority.allow_system(allow);
+    }
+    Ok(priority)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shiratsu_dat::nointro::FromNoIntro;
+    use shiratsu_dat::selection::{one_game_one_rom, Grouping};
+    use shiratsu_dat::GameEntry;
+
+    #[test]
+    fn loads_shipped_rules() {
+        let priority = load_priority(include_str!("../../1g1r.yml")).unwrap();
+        let games = ["Tetris (Japan)", "Tetris (Europe)", "Dr. Mario (Japan)", "Dr. Mario (World)"]
+            .iter()
+            .map(|name| {
+                format!(
+                    r#"<game name="{0}"><description>{0}</description><rom name="{0}.gb" size="1" crc="46DF91AD"/></game>"#,
+                    name
+                )
+            })
+            .collect::<String>();
+        let dat = format!("<datafile><header><homepage>No-Intro</homepage></header>{}</datafile>", games);
+        let entries = GameEntry::try_from_nointro_str(&dat)
+            .unwrap()
+            .into_iter()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .unwrap();
+        let selected = one_game_one_rom(&entries, Grouping::Title, &priority);
+        assert_eq!(
+            vec!["Tetris (Europe)", "Dr. Mario (World)"],
+            selected.iter().map(|e| e.entry_name()).collect::<Vec<_>>()
+        );
+        assert!(load_priority("regions: [Atlantis]").is_err());
+    }
+}
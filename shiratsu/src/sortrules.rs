@@ -5,10 +5,7 @@ use std::collections::HashMap;
 use std::result::Result;
 
 
+/// Loads the globs of DAT file names for each platform.
 pub fn load_map<S: AsRef<str>>(yaml_str: S) -> Result<HashMap<PlatformId, Vec<String>>, serde_yaml::Error> {
-    let mut map: HashMap<PlatformId, Vec<String>> = serde_yaml::from_str(yaml_str.as_ref())?;
-    for glob in map.values_mut().flat_map(|f| f) {
-        glob.insert_str(0, "unsorted/**/");
-    }
-    Ok(map)
+    serde_yaml::from_str(yaml_str.as_ref())
 }
\ No newline at end of file
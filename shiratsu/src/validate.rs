@@ -0,0 +1,508 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use shiratsu_stone::PlatformId;
+
+use std::collections::HashSet;
+use std::convert::TryInto;
+
+use anyhow::Result;
+
+/// The major version of the schema that databases are validated against.
+const SCHEMA_VERSION_MAJOR: u32 = 3;
+
+/// A table required by SPECIFICATION.md.
+struct RequiredTable {
+    /// The name of the table.
+    name: &'static str,
+    /// The minor schema version the table was added in.
+    since: u32,
+    /// The required columns, with the minor schema version each was added in.
+    columns: &'static [(&'static str, u32)],
+}
+
+/// The tables and columns required by SPECIFICATION.md.
+const REQUIRED_TABLES: &[RequiredTable] = &[
+    RequiredTable {
+        name: "game",
+        since: 0,
+        columns: &[
+            ("game_id", 0),
+            ("platform_id", 0),
+            ("entry_name", 0),
+            ("entry_title", 0),
+            ("release_title", 0),
+            ("region", 0),
+            ("part_number", 0),
+            ("is_unlicensed", 0),
+            ("is_demo", 0),
+            ("is_system", 0),
+            ("version", 0),
+            ("release_year", 5),
+            ("release_date", 5),
+            ("status", 0),
+            ("naming_convention", 0),
+            ("source", 0),
+            ("dat_id", 1),
+        ],
+    },
+    RequiredTable {
+        name: "rom",
+        since: 0,
+        columns: &[
+            ("file_name", 0),
+            ("mimetype", 0),
+            ("md5", 0),
+            ("crc", 0),
+            ("sha1", 0),
+            ("sha256", 8),
+            ("xxh3", 9),
+            ("size", 0),
+            ("status", 7),
+            ("game_id", 0),
+        ],
+    },
+    RequiredTable {
+        name: "serial",
+        since: 0,
+        columns: &[("serial", 0), ("normalized", 0), ("game_id", 0)],
+    },
+    RequiredTable {
+        name: "dat",
+        since: 1,
+        columns: &[
+            ("dat_id", 1),
+            ("file_name", 1),
+            ("name", 1),
+            ("version", 1),
+            ("date", 1),
+            ("sha1", 1),
+            ("source", 1),
+        ],
+    },
+    RequiredTable {
+        name: "clone_of",
+        since: 2,
+        columns: &[("game_id", 2), ("parent_id", 2)],
+    },
+    RequiredTable {
+        name: "language",
+        since: 4,
+        columns: &[("language", 4), ("game_id", 4)],
+    },
+    RequiredTable {
+        name: "publisher",
+        since: 5,
+        columns: &[("publisher", 5), ("game_id", 5)],
+    },
+    RequiredTable {
+        name: "dump_flag",
+        since: 6,
+        columns: &[("flag", 6), ("game_id", 6)],
+    },
+    RequiredTable {
+        name: "game_title",
+        since: 3,
+        columns: &[("entry_title", 3), ("release_title", 3)],
+    },
+    RequiredTable {
+        name: "shiragame",
+        since: 0,
+        columns: &[
+            ("shiragame", 0),
+            ("schema_version", 0),
+            ("stone_version", 0),
+            ("generated", 0),
+            ("release", 0),
+            ("aggregator", 0),
+            ("selection", 9),
+        ],
+    },
+];
+
+const STATUSES: &[&str] = &["release", "prerelease", "prototype"];
+const NAMING_CONVENTIONS: &[&str] = &["Unknown", "TOSEC", "No-Intro", "GoodTools"];
+const ROM_STATUSES: &[&str] = &["good", "baddump", "nodump", "verified"];
+const DUMP_FLAGS: &[&str] = &[
+    "bad",
+    "alternate",
+    "hack",
+    "trainer",
+    "cracked",
+    "fixed",
+    "pirate",
+    "translation",
+    "verified",
+];
+
+/// The number of offending rows listed in a single violation.
+const MAX_EXAMPLES: usize = 10;
+
+/// A single way a database does not conform to the specification.
+#[derive(Debug, Serialize)]
+pub struct Violation {
+    /// A short, stable name for the rule that was violated.
+    pub rule: &'static str,
+    pub message: String,
+    /// The number of offending rows, if the rule applies to rows.
+    pub count: Option<i64>,
+    /// Some of the offending values, if the rule applies to rows.
+    pub examples: Vec<String>,
+}
+
+impl Violation {
+    fn new(rule: &'static str, message: String) -> Violation {
+        Violation {
+            rule,
+            message,
+            count: None,
+            examples: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub schema_version: Option<String>,
+    pub violations: Vec<Violation>,
+}
+
+struct Validator<'a> {
+    conn: &'a Connection,
+    minor: u32,
+    violations: Vec<Violation>,
+}
+
+/// Validates a shiragame database against the specification.
+pub fn validate(conn: &Connection) -> Result<Report> {
+    let mut validator = Validator {
+        conn,
+        minor: 0,
+        violations: Vec::new(),
+    };
+    let schema_version = validator.check_meta()?;
+    validator.check_tables()?;
+    validator.check_references()?;
+    validator.check_values()?;
+    Ok(Report {
+        schema_version,
+        violations: validator.violations,
+    })
+}
+
+impl<'a> Validator<'a> {
+    fn has_table(&self, table: &str) -> Result<bool> {
+        Ok(self.conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE name = ? AND type IN ('table', 'view')",
+            params![table],
+            |row| row.get::<_, i64>(0),
+        )? > 0)
+    }
+
+    fn has_tables(&self, tables: &[&str]) -> Result<bool> {
+        for table in tables {
+            if !self.has_table(table)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Reports a violation for the rows returned by the query, if any.
+    ///
+    /// The query must return the offending values as a single column.
+    fn check_rows(&mut self, rule: &'static str, message: &str, sql: &str) -> Result<()> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let mut rows = stmt.query(params![])?;
+        let mut count = 0;
+        let mut examples = Vec::new();
+        while let Some(row) = rows.next()? {
+            count += 1;
+            if examples.len() < MAX_EXAMPLES {
+                let value: rusqlite::types::Value = row.get(0)?;
+                examples.push(match value {
+                    rusqlite::types::Value::Null => "NULL".to_string(),
+                    rusqlite::types::Value::Integer(i) => i.to_string(),
+                    rusqlite::types::Value::Real(f) => f.to_string(),
+                    rusqlite::types::Value::Text(s) => s,
+                    rusqlite::types::Value::Blob(_) => "<blob>".to_string(),
+                });
+            }
+        }
+        if count > 0 {
+            self.violations.push(Violation {
+                rule,
+                message: message.to_string(),
+                count: Some(count),
+                examples,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_meta(&mut self) -> Result<Option<String>> {
+        if !self.has_table("shiragame")? {
+            self.violations.push(Violation::new(
+                "table",
+                "Missing required table shiragame.".to_string(),
+            ));
+            return Ok(None);
+        }
+
+        let rows = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM shiragame", params![], |row| row.get::<_, i64>(0))?;
+        if rows != 1 {
+            self.violations.push(Violation::new(
+                "shiragame_rows",
+                format!("The shiragame table must have exactly one row, but has {}.", rows),
+            ));
+        }
+        if rows == 0 {
+            return Ok(None);
+        }
+
+        let (shiragame, schema_version): (Option<String>, Option<String>) = self.conn.query_row(
+            "SELECT shiragame, schema_version FROM shiragame LIMIT 1",
+            params![],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        if shiragame.as_deref() != Some("shiragame") {
+            self.violations.push(Violation::new(
+                "shiragame_rows",
+                "The shiragame column of the shiragame table must be the string \"shiragame\".".to_string(),
+            ));
+        }
+
+        match schema_version.as_deref().and_then(parse_version) {
+            Some((major, minor, _)) if major == SCHEMA_VERSION_MAJOR => self.minor = minor,
+            _ => self.violations.push(Violation::new(
+                "schema_version",
+                format!(
+                    "The schema version {} is not a semantic version compatible with ^{}.0.0.",
+                    schema_version.as_deref().unwrap_or("NULL"),
+                    SCHEMA_VERSION_MAJOR
+                ),
+            )),
+        }
+        Ok(schema_version)
+    }
+
+    fn check_tables(&mut self) -> Result<()> {
+        for table in REQUIRED_TABLES.iter() {
+            if table.since > self.minor {
+                continue;
+            }
+            if !self.has_table(table.name)? {
+                if table.name != "shiragame" {
+                    self.violations
+                        .push(Violation::new("table", format!("Missing required table {}.", table.name)));
+                }
+                continue;
+            }
+
+            let mut stmt = self.conn.prepare(&format!("PRAGMA table_info({})", table.name))?;
+            let present = stmt
+                .query_map(params![], |row| row.get::<_, String>(1))?
+                .collect::<rusqlite::Result<HashSet<_>>>()?;
+            // Columns of virtual tables are not listed by table_info.
+            if present.is_empty() {
+                continue;
+            }
+            for (column, since) in table.columns.iter() {
+                if *since <= self.minor && !present.contains(*column) {
+                    self.violations.push(Violation::new(
+                        "column",
+                        format!("Missing required column {}.{}.", table.name, column),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_references(&mut self) -> Result<()> {
+        for table in ["rom", "serial", "language", "publisher", "dump_flag"].iter() {
+            if self.has_tables(&[table, "game"])? {
+                self.check_rows(
+                    "game_id",
+                    &format!("Every {} row must refer to an existing game.", table),
+                    &format!(
+                        "SELECT {0}.game_id FROM {0} LEFT JOIN game ON game.game_id = {0}.game_id
+                        WHERE game.game_id IS NULL",
+                        table
+                    ),
+                )?;
+            }
+        }
+        if self.minor >= 2 && self.has_tables(&["clone_of", "game"])? {
+            self.check_rows(
+                "clone_of",
+                "Every clone_of row must refer to an existing game and parent.",
+                "SELECT clone_of.game_id FROM clone_of
+                LEFT JOIN game AS clone ON clone.game_id = clone_of.game_id
+                LEFT JOIN game AS parent ON parent.game_id = clone_of.parent_id
+                WHERE clone.game_id IS NULL OR parent.game_id IS NULL",
+            )?;
+        }
+        if self.minor >= 1 && self.has_tables(&["dat", "game"])? {
+            self.check_rows(
+                "dat_id",
+                "Every game row must refer to an existing dat.",
+                "SELECT game.game_id FROM game LEFT JOIN dat ON dat.dat_id = game.dat_id
+                WHERE dat.dat_id IS NULL",
+            )?;
+        }
+        Ok(())
+    }
+
+    fn check_values(&mut self) -> Result<()> {
+        if self.has_table("rom")? {
+            // sha256 was added in 3.8, xxh3 in 3.9, and nodump ROMs without hashes in 3.7.
+            let hashes: &[&str] = match self.minor {
+                minor if minor >= 9 => &["md5", "crc", "sha1", "sha256", "xxh3"],
+                8 => &["md5", "crc", "sha1", "sha256"],
+                _ => &["md5", "crc", "sha1"],
+            };
+            let nodump = if self.minor >= 7 { " AND status IS NOT 'nodump'" } else { "" };
+            self.check_rows(
+                "rom_hash",
+                &format!(
+                    "Every rom row{} must have at least one of {}.",
+                    if nodump.is_empty() { "" } else { " that is not a nodump" },
+                    hashes.join(", ")
+                ),
+                &format!(
+                    "SELECT file_name FROM rom WHERE {} IS NULL{}",
+                    hashes.join(" IS NULL AND "),
+                    nodump
+                ),
+            )?;
+        }
+        if self.has_table("rom")? && self.minor >= 7 {
+            self.check_rows(
+                "rom_status",
+                &format!("The status of every rom must be one of {}.", ROM_STATUSES.join(", ")),
+                &format!(
+                    "SELECT DISTINCT COALESCE(status, 'NULL') FROM rom WHERE status IS NULL OR status NOT IN ({})",
+                    sql_list(ROM_STATUSES)
+                ),
+            )?;
+        }
+        if self.has_table("language")? {
+            self.check_rows(
+                "language",
+                "Every language must be a lowercase ISO 639-1 code.",
+                "SELECT DISTINCT language FROM language
+                WHERE length(language) != 2 OR language GLOB '*[^a-z]*'",
+            )?;
+        }
+        if self.has_table("dump_flag")? {
+            self.check_rows(
+                "dump_flag",
+                &format!("Every dump flag must be one of {}.", DUMP_FLAGS.join(", ")),
+                &format!(
+                    "SELECT DISTINCT flag FROM dump_flag WHERE flag NOT IN ({})",
+                    sql_list(DUMP_FLAGS)
+                ),
+            )?;
+        }
+        if !self.has_table("game")? {
+            return Ok(());
+        }
+
+        if self.minor >= 5 {
+            self.check_rows(
+                "release_date",
+                "Every release date must be in the form YYYY, YYYY-MM or YYYY-MM-DD, with x for unknown digits.",
+                "SELECT DISTINCT release_date FROM game WHERE release_date IS NOT NULL
+                AND release_date NOT GLOB '[0-9x][0-9x][0-9x][0-9x]'
+                AND release_date NOT GLOB '[0-9x][0-9x][0-9x][0-9x]-[0-9x][0-9x]'
+                AND release_date NOT GLOB '[0-9x][0-9x][0-9x][0-9x]-[0-9x][0-9x]-[0-9x][0-9x]'",
+            )?;
+        }
+        self.check_rows(
+            "status",
+            &format!("The status of every game must be one of {}.", STATUSES.join(", ")),
+            &format!(
+                "SELECT DISTINCT COALESCE(status, 'NULL') FROM game WHERE status IS NULL OR status NOT IN ({})",
+                sql_list(STATUSES)
+            ),
+        )?;
+        self.check_rows(
+            "naming_convention",
+            &format!(
+                "The naming convention of every game must be one of {}.",
+                NAMING_CONVENTIONS.join(", ")
+            ),
+            &format!(
+                "SELECT DISTINCT COALESCE(naming_convention, 'NULL') FROM game
+                WHERE naming_convention IS NULL OR naming_convention NOT IN ({})",
+                sql_list(NAMING_CONVENTIONS)
+            ),
+        )?;
+
+        let mut stmt = self.conn.prepare("SELECT DISTINCT platform_id FROM game")?;
+        let unknown = stmt
+            .query_map(params![], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|platform_id| TryInto::<&PlatformId>::try_into(platform_id.as_str()).is_err())
+            .collect::<Vec<_>>();
+        if !unknown.is_empty() {
+            self.violations.push(Violation {
+                rule: "platform_id",
+                message: "The platform ID of every game must be a Stone platform ID.".to_string(),
+                count: Some(unknown.len() as i64),
+                examples: unknown.into_iter().take(MAX_EXAMPLES).collect(),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = version.split('.').map(|p| p.parse::<u32>().ok());
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(Some(major)), Some(Some(minor)), Some(Some(patch)), None) => Some((major, minor, patch)),
+        _ => None,
+    }
+}
+
+fn sql_list(values: &[&str]) -> String {
+    values
+        .iter()
+        .map(|v| format!("'{}'", v))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shiratsu::database::ShiratsuDatabase;
+
+    #[test]
+    fn validates_generated_database() {
+        let db = ShiratsuDatabase::new().unwrap();
+        let path = std::env::temp_dir().join(format!("shiratsu-validate-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        db.save(&path, None).unwrap();
+
+        let conn = Connection::open(&path).unwrap();
+        let report = validate(&conn).unwrap();
+        assert!(report.violations.is_empty(), "{:?}", report.violations);
+
+        conn.execute_batch(
+            "PRAGMA foreign_keys = OFF;
+            INSERT INTO rom (file_name, size, status, game_id) VALUES ('orphan.bin', 0, 'good', 42);
+            INSERT INTO rom (file_name, size, status, game_id) VALUES ('missing.bin', 0, 'nodump', 42);
+            INSERT INTO game (platform_id, entry_name, region, is_unlicensed, is_demo, is_system, status, naming_convention, source, dat_id)
+                VALUES ('NOT_A_PLATFORM', 'Test', 'US', 0, 0, 0, 'finished', 'No-Intro', 'Test', 1);",
+        )
+        .unwrap();
+        let report = validate(&conn).unwrap();
+        let rules = report.violations.iter().map(|v| v.rule).collect::<Vec<_>>();
+        assert_eq!(vec!["game_id", "dat_id", "rom_hash", "status", "platform_id"], rules);
+        std::fs::remove_file(&path).unwrap();
+    }
+}